@@ -0,0 +1,102 @@
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::{move_tree, StandardOptions, WalkError};
+
+/// How backups of replaced files and directories are named.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackupNaming {
+    /// Append a "~", or number the backup like `Numbered` when that name is taken
+    #[default]
+    Simple,
+    /// Append ".~1~", ".~2~", …, keeping earlier backups
+    Numbered,
+}
+
+/// Keeps files and directories which would otherwise be replaced.
+pub struct Backup {
+    pub naming: BackupNaming,
+    /// Where backups are kept instead of next to the replaced entries
+    pub directory: Option<Utf8PathBuf>,
+}
+
+impl Backup {
+    /// The backup settings from `--backup` and `--backup-dir`, `None` if neither was used.
+    pub fn configured(naming: Option<BackupNaming>, directory: Option<&str>) -> Option<Backup> {
+        if naming.is_none() && directory.is_none() {
+            return None;
+        }
+        Some(Backup {
+            naming: naming.unwrap_or_default(),
+            directory: directory.map(Utf8PathBuf::from),
+        })
+    }
+
+    /// The path the existing `path` is kept at, which neither exists nor is in `reserved`,
+    /// and which is added to it. Later backups to the same directory get the next free name.
+    pub fn path_for(&self, path: &Utf8Path, reserved: &mut Vec<Utf8PathBuf>) -> Utf8PathBuf {
+        let name = path.file_name().unwrap_or(path.as_str());
+        let directory = match &self.directory {
+            Some(directory) => directory.as_path(),
+            None => path.parent().unwrap_or(Utf8Path::new("")),
+        };
+        let simple = directory.join(format!("{}~", name));
+        let backup = if self.naming == BackupNaming::Simple
+            && simple.symlink_metadata().is_err()
+            && !reserved.contains(&simple)
+        {
+            simple
+        } else {
+            // An earlier backup is kept rather than replaced
+            (last_backup_number(directory, name) + 1..)
+                .map(|number| directory.join(format!("{}.~{}~", name, number)))
+                .find(|backup| !reserved.contains(backup))
+                .unwrap()
+        };
+        reserved.push(backup.clone());
+        backup
+    }
+}
+
+/// Moves the existing `path` to `backup`.
+pub(crate) fn move_to_backup(
+    options: &StandardOptions,
+    path: &Utf8Path,
+    backup: &Utf8Path,
+) -> Result<(), Vec<WalkError>> {
+    let fail = |error| {
+        vec![WalkError {
+            path: path.to_owned(),
+            error,
+        }]
+    };
+    if let Some(directory) = backup.parent() {
+        std::fs::create_dir_all(directory).map_err(fail)?;
+    }
+    match std::fs::rename(path, backup) {
+        Err(error) if error.kind() == std::io::ErrorKind::CrossesDevices => {
+            move_tree(options, path, backup)
+        }
+        result => result.map_err(fail),
+    }
+}
+
+// The highest N of the existing "name.~N~" backups in `directory`, 0 if there are none
+fn last_backup_number(directory: &Utf8Path, name: &str) -> u64 {
+    let directory = if directory.as_str().is_empty() {
+        Utf8Path::new(".")
+    } else {
+        directory
+    };
+    let Ok(entries) = directory.read_dir_utf8() else {
+        return 0;
+    };
+    let prefix = format!("{}.~", name);
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let number = entry.file_name().strip_prefix(&prefix)?.strip_suffix('~')?;
+            number.parse().ok()
+        })
+        .max()
+        .unwrap_or(0)
+}
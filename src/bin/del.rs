@@ -1,10 +1,6 @@
-use camino::Utf8Path;
+use camino::{Utf8Path, Utf8PathBuf};
 use clap::Parser;
-use colored::*;
-use human_utils::{
-    confirm_or_exit, directory_path, message_success, path_string, StandardOptions, FAILURE,
-    SUCCESS,
-};
+use human_utils::{DelOptions, StandardOptions, TreePreview};
 
 // TODO: Support `del .` and `del ..`
 
@@ -26,6 +22,12 @@ Examples where `del` differs from `rm`:
     will delete `a`, while `rm a`
     will error out and request the use of `-r` option.
 
+  Protects git work:
+    `del a` where a contains a git repository with
+    modified or untracked files, stashes or commits
+    which are on no remote, `del` will list them and
+    ask to type `delete` instead of a plain yes.
+
 Exits with non-zero (failure) value if no files/directories were
 removed or if some existing files/directories were not removed.
 ";
@@ -34,6 +36,7 @@ removed or if some existing files/directories were not removed.
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 #[clap(after_long_help = DETAILS)]
+#[allow(clippy::upper_case_acronyms)]
 struct CLI {
     /// The paths to one or more files/directories
     // #[tested(rem_requires_at_least_one_argument)]
@@ -43,6 +46,20 @@ struct CLI {
     #[arg(long, hide = true)]
     track_cwd_change: Option<String>,
 
+    /// Search SCOPE for symlinks pointing into the deleted paths and warn about them.
+    /// SCOPE defaults to the enclosing git repository, or the current directory outside of one.
+    #[arg(long, value_name = "SCOPE", num_args = 0..=1, require_equals = true)]
+    check_links: Option<Option<String>>,
+
+    /// List the running processes which hold the deleted paths open before asking.
+    #[arg(long)]
+    check_open: bool,
+
+    /// Stage the removals in the git index like `git rm`, the default if the repository
+    /// sets the `human-utils.git` config to true.
+    #[arg(long)]
+    git: bool,
+
     #[command(flatten)]
     options: StandardOptions,
 }
@@ -53,145 +70,29 @@ fn main() {
 
     let paths: Vec<&Utf8Path> = args.file_or_directory.iter().map(Utf8Path::new).collect();
     human_utils::set_color_override(options);
-    ask_to_confirm(options, &paths);
+    let del_options = DelOptions {
+        check_links: args.check_links.as_ref().map(|scope| {
+            scope
+                .as_ref()
+                .map_or_else(human_utils::default_link_scope, Utf8PathBuf::from)
+        }),
+        check_open: args.check_open,
+        git: args.git,
+    };
     let original_cwd = human_utils::get_cwd();
-    let all_removed = remove_all(options, &paths);
-    track_cwd_change(args, original_cwd);
-
-    std::process::exit(if all_removed { SUCCESS } else { FAILURE });
-}
-
-fn ask_to_confirm(options: &StandardOptions, paths: &Vec<&Utf8Path>) {
-    if options.force {
-        return;
-    }
-
-    let is_single = paths.len() == 1;
-    if is_single {
-        let path = paths.first().unwrap();
-        ask_for_single_path(&path);
-    } else {
-        ask_for_multiple_paths(&paths);
-    }
-
-    // #[tested(rem_basic)]
-    confirm_or_exit();
-}
-
-fn ask_for_single_path(path: &Utf8Path) {
-    match path.symlink_metadata() {
-        Ok(metadata) => {
-            let file_type = if metadata.is_dir() {
-                "directory"
-            } else {
-                "file"
-            };
-            // #[tested(rem_basic)]
-            print!("Delete {} \"{}\"? [Y/n]", file_type, path);
-        }
-        Err(error) => {
-            // #[tested(rem_no_existing::nonexistent_path_fails)]
-            eprintln!("Error for \"{}\": {}", path, error);
-            std::process::exit(FAILURE);
-        }
-    }
-}
-
-fn ask_for_multiple_paths(paths: &Vec<&Utf8Path>) {
-    println!("For the following...");
-    let mut all_exist = true;
-    let mut some_exist = false;
-    for path in paths {
-        let metadata = path.symlink_metadata();
-        print_path(path, &metadata);
-        let exists = metadata.is_ok();
-        all_exist = all_exist && exists;
-        some_exist = some_exist || exists;
-    }
-    if !some_exist {
-        // #[tested(rem_no_existing)]
-        eprintln!("...no files or directories can be removed.");
-        std::process::exit(FAILURE);
-    }
-    // #[tested(rem_basic)]
-    print!(
-        "...delete all{}? [Y/n]",
-        if all_exist { "" } else { " existing" }
-    );
-}
-
-fn print_path(path: &Utf8Path, metadata: &std::io::Result<std::fs::Metadata>) {
-    match metadata {
-        Ok(metadata) => {
-            // #[tested(rem_multiple)]
-            if metadata.is_dir() {
-                println!("{}", directory_path(path));
-            } else {
-                println!("{}", path_string(path));
-            }
-        }
-        Err(error) => {
-            // #[tested(rem_no_existing)]
-            eprintln!("\"{}\" error: {}", path, error);
-        }
-    }
-}
-
-// #[tested(TODO)]
-fn remove_all(options: &StandardOptions, paths: &Vec<&Utf8Path>) -> bool {
-    let mut all_removed = true;
-    for path in paths {
-        let removed = if let Ok(metadata) = path.symlink_metadata() {
-            if metadata.is_dir() {
-                remove_dir(options, &path)
-            } else {
-                remove_file(options, &path)
-            }
-        } else {
-            // Consider non-existing files and directories as removed
-            true
-        };
-        if !removed {
-            all_removed = false;
-        }
-    }
-    all_removed
-}
-
-fn remove_dir(options: &StandardOptions, path: &Utf8Path) -> bool {
-    if !options.dry_run {
-        let path = human_utils::handle_cwd(path);
-        let path = path.as_ref();
-        // #[tested(rem_basic)]
-        if let Err(error) = std::fs::remove_dir_all(path) {
-            // #[tested(TODO)]
-            eprintln!("Error for directory \"{}\": {}", path, error);
-            return false;
-        }
-    }
-    message_success!(
+    let report = human_utils::exit_on_error(human_utils::del(
         options,
-        "{}",
-        format!("D {}", directory_path(path)).bright_red()
-    );
-    true
-}
+        &del_options,
+        &paths,
+        &mut |question| human_utils::ask_in_terminal(options, question),
+    ));
+    track_cwd_change(args, original_cwd);
+    human_utils::print_report(options, &report);
+    let mut preview = TreePreview::new(options);
+    preview.add_report(&report);
+    preview.print(colored::Color::BrightRed);
 
-fn remove_file(options: &StandardOptions, path: &Utf8Path) -> bool {
-    if !options.dry_run {
-        // #[tested(rem_basic)]
-        if let Err(error) = std::fs::remove_file(path) {
-            // #[tested(TODO)]
-            eprintln!("Error for file \"{}\": {}", path, error);
-            return false;
-        }
-    }
-    message_success!(
-        options,
-        "{}",
-        format!("D {}", path_string(path)).bright_red()
-    );
-    true
+    std::process::exit(report.exit_code(options));
 }
 
 fn track_cwd_change(args: &CLI, original_cwd: std::path::PathBuf) {
@@ -1,7 +1,8 @@
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::{CommandFactory, Parser};
-use colored::Colorize;
-use human_utils::{message_success, path_string, StandardOptions, FAILURE, SUCCESS};
+use human_utils::{
+    Backup, BackupNaming, Destination, MovOptions, OnConflict, TreePreview, FAILURE, SUCCESS,
+};
 
 // TODO: Support `mov . something_else` and `mov something_else .`
 
@@ -67,6 +68,66 @@ struct HelpCLI {
     #[arg(short, long, value_name = "PATH")]
     to: Option<String>,
 
+    /// Rewrite relative symlinks which would no longer resolve after the move
+    /// without asking for confirmation, including those inside moved directories.
+    #[arg(long)]
+    fix_links: bool,
+
+    /// Search SCOPE for symlinks pointing into the moved paths and offer to retarget them,
+    /// and the moved directories for relative symlinks which would no longer resolve.
+    /// SCOPE defaults to the enclosing git repository, or the current directory outside of one.
+    #[arg(long, value_name = "SCOPE", num_args = 0..=1, require_equals = true)]
+    check_links: Option<Option<String>>,
+
+    /// Ask before moving or replacing paths which running processes hold open,
+    /// listing them along with the other confirmations.
+    #[arg(long)]
+    check_open: bool,
+
+    /// Move the contents of directories into existing directories instead of replacing them,
+    /// asking before overwriting each conflicting file.
+    #[arg(long)]
+    merge: bool,
+
+    /// What to do when a file or directory already exists at the destination.
+    #[arg(
+        long,
+        value_name = "STRATEGY",
+        default_value = "ask",
+        conflicts_with = "merge"
+    )]
+    on_conflict: OnConflict,
+
+    /// Keep replaced files and directories as "name~", or as "name.~1~", "name.~2~", … with `numbered`.
+    #[arg(
+        long,
+        value_name = "NAMING",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "simple",
+        conflicts_with = "merge"
+    )]
+    backup: Option<BackupNaming>,
+
+    /// Keep the backups of replaced files and directories in DIR, implies --backup.
+    #[arg(long, value_name = "DIR", conflicts_with = "merge")]
+    backup_dir: Option<String>,
+
+    /// When moving to another file system, compare the SHA-256 checksums of the copies
+    /// to the originals before removing them.
+    #[arg(long, conflicts_with = "merge")]
+    verify: bool,
+
+    /// With --verify, write the checksums of the moved files to DESTINATION.sha256
+    /// in the format of `sha256sum`.
+    #[arg(long, requires = "verify")]
+    manifest: bool,
+
+    /// Update the git index like `git mv`, the default if the repository
+    /// sets the `human-utils.git` config to true.
+    #[arg(long, conflicts_with = "merge")]
+    git: bool,
+
     #[command(flatten)]
     options: human_utils::StandardOptions,
 }
@@ -77,6 +138,7 @@ struct HelpCLI {
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 #[clap(disable_help_flag = true)]
+#[allow(clippy::upper_case_acronyms)]
 struct CLI {
     #[arg()]
     paths: Vec<String>,
@@ -90,6 +152,42 @@ struct CLI {
     #[arg(long)]
     track_cwd_change: Option<String>,
 
+    #[arg(long)]
+    fix_links: bool,
+
+    #[arg(long, num_args = 0..=1, require_equals = true)]
+    check_links: Option<Option<String>>,
+
+    #[arg(long)]
+    check_open: bool,
+
+    #[arg(long)]
+    merge: bool,
+
+    #[arg(long, default_value = "ask", conflicts_with = "merge")]
+    on_conflict: OnConflict,
+
+    #[arg(
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "simple",
+        conflicts_with = "merge"
+    )]
+    backup: Option<BackupNaming>,
+
+    #[arg(long, conflicts_with = "merge")]
+    backup_dir: Option<String>,
+
+    #[arg(long, conflicts_with = "merge")]
+    verify: bool,
+
+    #[arg(long, requires = "verify")]
+    manifest: bool,
+
+    #[arg(long, conflicts_with = "merge")]
+    git: bool,
+
     #[command(flatten)]
     options: human_utils::StandardOptions,
 
@@ -116,34 +214,45 @@ fn main() {
     at_least_one_source(&paths);
     let sources: &Vec<_> = &paths.iter().map(Utf8Path::new).collect();
     human_utils::set_color_override(&args.options);
-    if let Some(destination) = into {
-        let destination = Utf8Path::new(&destination);
-        check_sources_exists(sources);
-        check_sources_already_at_destination(options, sources, destination);
-        let paths_at_destination = &get_paths_at_destination(sources, destination);
-        human_utils::check_paths_exist_and_confirm_or_exit(options, paths_at_destination);
-        let existing_ancestor =
-            human_utils::find_existing_or_ancestor_for_print(options, destination);
-        human_utils::create_directory(options, destination);
-        let original_cwd = human_utils::get_cwd();
-        rename_all(options, sources, paths_at_destination);
-        track_cwd_change(args, original_cwd);
-        print_success_all(options, sources, paths_at_destination, existing_ancestor);
-    } else {
-        let destination = to.unwrap();
-        let destination = Utf8Path::new(&destination);
-        let source = only_one_source(args, sources);
-        let canonical_source = check_source_exists(source);
-        check_source_already_at_destination(options, source, &canonical_source, destination);
-        human_utils::check_path_exists_and_confirm_or_exit(options, destination);
-        let existing_ancestor =
-            human_utils::find_existing_ancestor_directory_for_print(options, destination);
-        human_utils::create_parent_directory(options, destination);
-        let original_cwd = human_utils::get_cwd();
-        rename(options, source, destination);
-        track_cwd_change(args, original_cwd);
-        print_success(options, source, destination, existing_ancestor);
+    let destination = match (&into, &to) {
+        (Some(into), _) => Destination::Into(Utf8Path::new(into)),
+        (None, to) => {
+            only_one_source(args, sources);
+            Destination::To(Utf8Path::new(to.as_ref().unwrap()))
+        }
+    };
+    let mov_options = MovOptions {
+        on_conflict: args.on_conflict,
+        merge: args.merge,
+        backup: Backup::configured(args.backup, args.backup_dir.as_deref()),
+        verify: args.verify,
+        manifest: args.manifest,
+        git: args.git,
+        fix_links: args.fix_links,
+        check_links: args.check_links.as_ref().map(|scope| {
+            scope
+                .as_ref()
+                .map_or_else(human_utils::default_link_scope, Utf8PathBuf::from)
+        }),
+        check_open: args.check_open,
+    };
+    let original_cwd = human_utils::get_cwd();
+    let report = human_utils::exit_on_error(human_utils::mov(
+        options,
+        &mov_options,
+        sources,
+        destination,
+        &mut |question| human_utils::ask_in_terminal(options, question),
+    ));
+    track_cwd_change(args, original_cwd);
+
+    human_utils::print_report(options, &report);
+    if !report.failed.is_empty() {
+        std::process::exit(report.exit_code(options));
     }
+    let mut preview = TreePreview::new(options);
+    preview.add_report(&report);
+    preview.print(COLOR);
 
     std::process::exit(SUCCESS);
 }
@@ -174,14 +283,14 @@ fn determine_destination_type(args: &CLI) -> (Vec<String>, Option<String>, Optio
     (paths, into, to)
 }
 
-fn at_least_one_source(paths: &Vec<String>) {
-    if paths.len() == 0 {
+fn at_least_one_source(paths: &[String]) {
+    if paths.is_empty() {
         eprintln!("Error: Expected at least one SOURCE_PATH, got only a destination PATH");
         std::process::exit(FAILURE);
     }
 }
 
-fn only_one_source<'a>(args: &CLI, sources: &'a Vec<&'a Utf8Path>) -> &'a Utf8Path {
+fn only_one_source(args: &CLI, sources: &[&Utf8Path]) {
     if sources.len() != 1 {
         eprintln!(
             "Error: Expected 1 SOURCE_PATH argument because {}, but got {}",
@@ -197,145 +306,10 @@ fn only_one_source<'a>(args: &CLI, sources: &'a Vec<&'a Utf8Path>) -> &'a Utf8Pa
         );
         std::process::exit(FAILURE);
     }
-    sources[0]
-}
-
-fn check_sources_exists(sources: &Vec<&Utf8Path>) {
-    for source in sources {
-        check_source_exists(source);
-    }
-}
-
-fn check_source_exists(source: &Utf8Path) -> Utf8PathBuf {
-    match source.canonicalize_utf8() {
-        Ok(source) => source,
-        Err(error) => {
-            eprintln!("Error for \"{}\": {}", source, error);
-            std::process::exit(FAILURE);
-        }
-    }
-}
-
-fn check_sources_already_at_destination(
-    options: &StandardOptions,
-    sources: &Vec<&Utf8Path>,
-    destination: &Utf8Path,
-) {
-    if let Ok(destination_canonical) = destination.canonicalize_utf8() {
-        let mut all_sources_already_at_destination = true;
-        for source in sources {
-            let source_canonical = source.canonicalize_utf8().unwrap();
-            let source_parent = source_canonical.parent().unwrap();
-            if source_parent.eq(&destination_canonical) {
-                message_success!(
-                    options,
-                    "\"{}\" is already located at \"{}\"",
-                    source,
-                    human_utils::directory_path(destination)
-                );
-            } else {
-                all_sources_already_at_destination = false;
-            }
-        }
-        if all_sources_already_at_destination {
-            std::process::exit(SUCCESS);
-        }
-    }
-}
-
-fn check_source_already_at_destination(
-    options: &StandardOptions,
-    source: &Utf8Path,
-    canonical_source: &Utf8Path,
-    destination: &Utf8Path,
-) {
-    if let Ok(canonical_destination) = destination.canonicalize_utf8() {
-        if canonical_source == canonical_destination {
-            message_success!(
-                options,
-                "\"{}\" is already located at \"{}\"",
-                source,
-                destination
-            );
-            std::process::exit(SUCCESS);
-        }
-    }
-}
-
-fn get_paths_at_destination(sources: &Vec<&Utf8Path>, destination: &Utf8Path) -> Vec<Utf8PathBuf> {
-    sources
-        .iter()
-        .map(|source| destination.join(source.file_name().unwrap()))
-        .collect()
-}
-
-fn rename_all(
-    options: &StandardOptions,
-    sources: &Vec<&Utf8Path>,
-    paths_at_destination: &Vec<Utf8PathBuf>,
-) {
-    for (i, source) in sources.iter().enumerate() {
-        let destination_path = &paths_at_destination[i];
-        rename(options, source, &destination_path);
-    }
-}
-
-fn rename(options: &StandardOptions, from: &Utf8Path, to: &Utf8Path) {
-    // TODO: Make sure this works correctly on Windows
-    // if to.exists() && to.is_dir() {
-    //     // normalize behavior between *nix and windows
-    //     if from.is_dir()
-    //             fs::remove_dir(to)?;
-    //     }
-    // }
-    if options.dry_run {
-        return;
-    }
-
-    let from = human_utils::handle_cwd(from);
-    let from = from.as_ref();
-
-    if std::fs::rename(from, to).is_err() {
-        if to.exists() {
-            if to.is_dir() {
-                std::fs::remove_dir_all(to).unwrap();
-            } else {
-                std::fs::remove_file(to).unwrap();
-            }
-        }
-        std::fs::rename(from, to).unwrap();
-    }
 }
 
 const COLOR: colored::Color = colored::Color::BrightGreen;
 
-fn print_success_all(
-    options: &StandardOptions,
-    sources: &Vec<&Utf8Path>,
-    paths_at_destination: &Vec<Utf8PathBuf>,
-    existing_ancestor: Option<&Utf8Path>,
-) {
-    for (i, source) in sources.iter().enumerate() {
-        let destination_path = &paths_at_destination[i];
-        print_success(options, source, destination_path, existing_ancestor);
-    }
-}
-
-fn print_success(
-    options: &StandardOptions,
-    source: &Utf8Path,
-    destination: &Utf8Path,
-    existing_ancestor: Option<&Utf8Path>,
-) {
-    message_success!(
-        options,
-        "{} {} -> {}",
-        "M".color(COLOR),
-        path_string(source).bright_red(),
-        human_utils::color_new(destination, existing_ancestor, COLOR)
-    );
-}
-
 fn track_cwd_change(args: &CLI, original_cwd: std::path::PathBuf) {
     let new_cwd = human_utils::get_cwd();
     if let Some(tracking_file_path) = args.track_cwd_change.as_ref() {
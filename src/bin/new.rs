@@ -1,9 +1,6 @@
-use std::collections::{BTreeMap, BTreeSet};
-
-use camino::{Utf8Path, Utf8PathBuf};
+use camino::Utf8PathBuf;
 use clap::{ArgAction, Args, Parser};
-use colored::Colorize;
-use human_utils::{message_success, StandardOptions, FAILURE, SUCCESS};
+use human_utils::{Backup, BackupNaming, NewOptions, OnConflict, TreePreview, FAILURE, SUCCESS};
 use itertools::{Either, Itertools};
 
 const DETAILS: &str = "
@@ -30,6 +27,7 @@ const FILE_HELP: &str = const_format::formatcp!(
 #[derive(Parser)]
 #[command(author, version, about)]
 #[clap(after_long_help = DETAILS)]
+#[allow(clippy::upper_case_acronyms)]
 struct CLI {
     #[command(flatten)]
     names: Names,
@@ -39,6 +37,38 @@ struct CLI {
     #[arg(last(true))]
     content: Vec<String>,
 
+    /// What to do when a file or directory already exists at one of the paths.
+    /// With `rename`, directories which cannot be created because of an existing file are skipped.
+    #[arg(long, value_name = "STRATEGY", default_value = "ask")]
+    on_conflict: OnConflict,
+
+    /// Keep replaced files and directories as "name~", or as "name.~1~", "name.~2~", … with `numbered`.
+    #[arg(
+        long,
+        value_name = "NAMING",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "simple"
+    )]
+    backup: Option<BackupNaming>,
+
+    /// Keep the backups of replaced files and directories in DIR, implies --backup.
+    #[arg(long, value_name = "DIR")]
+    backup_dir: Option<String>,
+
+    /// Add the new files to the git index like `git add`, the default if the repository
+    /// sets the `human-utils.git` config to true.
+    #[arg(long)]
+    git: bool,
+
+    #[arg(long, hide = true)]
+    track_cwd_change: Option<String>,
+
+    /// Change the working directory to the created or existing directory
+    /// when used through the shell integration.
+    #[arg(long, requires = "track_cwd_change")]
+    enter: bool,
+
     #[command(flatten)]
     options: human_utils::StandardOptions,
 }
@@ -64,126 +94,66 @@ fn main() {
     let CLI {
         names,
         content,
+        on_conflict,
+        backup,
+        backup_dir,
+        git,
+        track_cwd_change,
+        enter,
         options,
     } = CLI::parse();
-
-    let (all_directory_paths, directory_paths, file_paths) = combine_input_paths(names);
+    let (directory_paths, file_paths) = split_input_paths(names);
+    let entered = enter.then(|| only_one_directory(&directory_paths));
 
     human_utils::set_color_override(&options);
 
-    check_argument_conflicts(&all_directory_paths, &file_paths);
-
-    let (clashing_with_directories, clashing_with_files) =
-        check_conflicts(&options, &all_directory_paths, &file_paths);
-
-    delete_clashing(&options, &clashing_with_directories, &clashing_with_files);
-
-    for path in directory_paths {
-        let clashing = clashing_with_directories.get(&path);
-        if clashing.map_or(false, |metadata| metadata.is_dir()) {
-            message_success!(options, "Directory \"{}\" already exists", path);
-            continue;
-        }
-        if !options.dry_run {
-            std::fs::create_dir_all(&path).unwrap();
-        }
-        print_success(
-            &options,
-            &human_utils::directory_path(&path),
-            None,
-            find_existing_ancestor_directory_for_printout(
-                &options,
-                &path,
-                &clashing_with_directories,
-            ),
-        );
-    }
-
-    let content = if content.is_empty() {
-        None
-    } else if content.len() == 1 && content[0] == "" {
-        None
-    } else {
-        Some(content.join(" ") + "\n")
+    let new_options = NewOptions {
+        content: if content.is_empty() || (content.len() == 1 && content[0].is_empty()) {
+            None
+        } else {
+            Some(content.join(" ") + "\n")
+        },
+        on_conflict,
+        backup: Backup::configured(backup, backup_dir.as_deref()),
+        git,
     };
-    for path in file_paths {
-        let clashing = clashing_with_files.get(&path);
-        if content.is_none()
-            && clashing.map_or(false, |metadata| !metadata.is_dir() && metadata.len() == 0)
-        {
-            message_success!(options, "File \"{}\" already exists", path);
-            continue;
-        }
-        human_utils::create_file(&options, &path, content.as_deref());
-        print_success(
-            &options,
-            &path,
-            clashing,
-            find_existing_ancestor_directory_for_printout(
-                &options,
-                &path,
-                &clashing_with_directories,
-            ),
-        );
+    let report = human_utils::exit_on_error(human_utils::new(
+        &options,
+        &new_options,
+        &directory_paths,
+        &file_paths,
+        &mut |question| human_utils::ask_in_terminal(&options, question),
+    ));
+
+    human_utils::print_report(&options, &report);
+    if !report.failed.is_empty() {
+        std::process::exit(report.exit_code(&options));
     }
-    std::process::exit(SUCCESS);
-}
-
-fn delete_clashing(
-    options: &StandardOptions,
-    clashing_with_directories: &BTreeMap<Utf8PathBuf, std::fs::Metadata>,
-    clashing_with_files: &BTreeMap<Utf8PathBuf, std::fs::Metadata>,
-) {
-    for (path, metadata) in clashing_with_directories {
-        if !metadata.is_dir() {
-            message_success!(
-                options,
-                "{}",
-                format!("D {}", human_utils::format_path(path, metadata)).bright_red()
-            );
-            if options.dry_run {
-                continue;
-            }
-            std::fs::remove_file(path).unwrap();
-        }
-    }
-    for (path, metadata) in clashing_with_files {
-        if metadata.is_dir() {
-            message_success!(
-                options,
-                "{}",
-                format!("D {}", human_utils::format_path(path, metadata)).bright_red()
-            );
-            if options.dry_run {
-                continue;
-            }
-            std::fs::remove_dir_all(path).unwrap();
+    if let (Some(tracking_file_path), Some(directory)) = (track_cwd_change, entered) {
+        if !options.dry_run && directory.is_dir() {
+            let new_cwd = human_utils::absolute_path(directory);
+            std::fs::write(tracking_file_path, new_cwd.as_str()).unwrap();
         }
     }
+    let mut preview = TreePreview::new(&options);
+    preview.add_report(&report);
+    preview.print(COLOR);
+    std::process::exit(SUCCESS);
 }
 
-fn check_argument_conflicts(
-    all_directory_paths: &BTreeSet<Utf8PathBuf>,
-    file_paths: &BTreeSet<Utf8PathBuf>,
-) {
-    let clashing: BTreeSet<_> = all_directory_paths.intersection(file_paths).collect();
-
-    if !clashing.is_empty() {
+fn only_one_directory(directory_paths: &[Utf8PathBuf]) -> &Utf8PathBuf {
+    let [directory] = directory_paths else {
         eprintln!(
-            "Error: Cannot create both file and a directory at:\n{}",
-            clashing.into_iter().join("\n")
+            "Error: Expected 1 directory to enter, but got {}",
+            directory_paths.len()
         );
         std::process::exit(FAILURE);
-    }
+    };
+    directory
 }
 
-fn combine_input_paths(
-    names: Names,
-) -> (
-    BTreeSet<Utf8PathBuf>,
-    BTreeSet<Utf8PathBuf>,
-    BTreeSet<Utf8PathBuf>,
-) {
+/// Splits the paths into directories, which end in a separator, and files.
+fn split_input_paths(names: Names) -> (Vec<Utf8PathBuf>, Vec<Utf8PathBuf>) {
     let Names {
         paths,
         file,
@@ -199,121 +169,19 @@ fn combine_input_paths(
             std::process::exit(FAILURE);
         }
     }
-    let (mut directory_path_strings, mut file_path_strings): (BTreeSet<_>, BTreeSet<_>) =
+    let (mut directory_paths, mut file_paths): (Vec<_>, Vec<_>) =
         paths.into_iter().partition_map(|path| {
             if path.ends_with(std::path::MAIN_SEPARATOR) {
-                Either::Left(path.trim_end_matches(std::path::MAIN_SEPARATOR).to_string())
+                Either::Left(Utf8PathBuf::from(
+                    path.trim_end_matches(std::path::MAIN_SEPARATOR),
+                ))
             } else {
-                Either::Right(path)
+                Either::Right(Utf8PathBuf::from(path))
             }
         });
-    directory_path_strings.extend(directory);
-    file_path_strings.extend(file);
-    let directory_paths: BTreeSet<_> = directory_path_strings
-        .iter()
-        .map(Utf8PathBuf::from)
-        .collect();
-    let file_paths: BTreeSet<_> = file_path_strings.iter().map(Utf8PathBuf::from).collect();
-
-    let all_directory_paths: BTreeSet<_> = directory_paths
-        .iter()
-        .flat_map(|path| path.ancestors())
-        .map(|path| path.to_owned())
-        .chain(file_paths.iter().flat_map(|path| {
-            path.parent()
-                .map(|parent| parent.ancestors())
-                .into_iter()
-                .flatten()
-                .map(|path| path.to_owned())
-        }))
-        .filter(|path| path != "")
-        .collect();
-
-    (all_directory_paths, directory_paths, file_paths)
-}
-
-fn check_conflicts(
-    options: &StandardOptions,
-    all_directory_paths: &BTreeSet<Utf8PathBuf>,
-    file_paths: &BTreeSet<Utf8PathBuf>,
-) -> (
-    BTreeMap<Utf8PathBuf, std::fs::Metadata>,
-    BTreeMap<Utf8PathBuf, std::fs::Metadata>,
-) {
-    let clashing_with_directories = all_directory_paths
-        .iter()
-        .filter_map(|path| {
-            path.symlink_metadata()
-                .ok()
-                .map(|metadata| (path.clone(), metadata))
-        })
-        .collect::<BTreeMap<_, _>>();
-
-    let clashing_with_files = file_paths
-        .iter()
-        .filter_map(|path| {
-            path.symlink_metadata()
-                .ok()
-                .map(|metadata| (path.clone(), metadata))
-        })
-        .collect::<BTreeMap<_, _>>();
-
-    if options.force {
-        return (clashing_with_directories, clashing_with_files);
-    }
-
-    human_utils::ask_to_overwrite(
-        &clashing_with_directories
-            .iter()
-            .filter(|(_, metadata)| !metadata.is_dir())
-            .map(|(path, _)| path)
-            .chain(
-                clashing_with_files
-                    .iter()
-                    .filter(|(_, metadata)| metadata.is_dir() || metadata.len() > 0)
-                    .map(|(path, _)| path),
-            )
-            .collect(),
-    );
-
-    (clashing_with_directories, clashing_with_files)
-}
-
-pub fn find_existing_ancestor_directory_for_printout<'a>(
-    options: &StandardOptions,
-    path: &'a Utf8Path,
-    existing_ancestors: &BTreeMap<Utf8PathBuf, std::fs::Metadata>,
-) -> Option<&'a Utf8Path> {
-    if options.no_color {
-        return None;
-    }
-
-    let mut ancestor = path.parent();
-    while let Some(ancestor_path) = ancestor {
-        if existing_ancestors
-            .get(ancestor_path)
-            .map_or(false, |metadata| metadata.is_dir())
-        {
-            return Some(ancestor_path);
-        }
-        ancestor = ancestor_path.parent();
-    }
-    None
+    directory_paths.extend(directory.into_iter().map(Utf8PathBuf::from));
+    file_paths.extend(file.into_iter().map(Utf8PathBuf::from));
+    (directory_paths, file_paths)
 }
 
 const COLOR: colored::Color = colored::Color::BrightGreen;
-
-fn print_success(
-    options: &StandardOptions,
-    created: &Utf8Path,
-    clashing: Option<&std::fs::Metadata>,
-    existing_ancestor: Option<&Utf8Path>,
-) {
-    let did_overwite_file = clashing.map_or(false, |metadata| !metadata.is_dir());
-    message_success!(
-        options,
-        "{} {}",
-        (if did_overwite_file { "E" } else { "N" }).color(COLOR),
-        human_utils::color_new(created, existing_ancestor, COLOR)
-    );
-}
@@ -0,0 +1,149 @@
+use std::time::SystemTime;
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::summarize_tree;
+
+/// What to do when a file or directory already exists where an entry would be written.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnConflict {
+    /// Ask before overwriting
+    #[default]
+    Ask,
+    /// Keep the existing entry
+    Skip,
+    /// Overwrite without asking
+    Overwrite,
+    /// Keep both, writing to a free name like "name (1).ext"
+    Rename,
+    /// Overwrite if the new entry was modified more recently
+    Newer,
+    /// Overwrite if the new entry is larger
+    Larger,
+}
+
+impl OnConflict {
+    fn name(self) -> &'static str {
+        match self {
+            OnConflict::Ask => "ask",
+            OnConflict::Skip => "skip",
+            OnConflict::Overwrite => "overwrite",
+            OnConflict::Rename => "rename",
+            OnConflict::Newer => "newer",
+            OnConflict::Larger => "larger",
+        }
+    }
+}
+
+/// The entry which would be written to a destination path.
+pub enum Incoming<'a> {
+    /// A file or directory which is moved or copied
+    Path(&'a Utf8Path),
+    /// A new file with content of the given size
+    Content(u64),
+}
+
+impl Incoming<'_> {
+    fn modified(&self) -> Option<SystemTime> {
+        match self {
+            Incoming::Path(path) => path.symlink_metadata().ok()?.modified().ok(),
+            Incoming::Content(_) => Some(SystemTime::now()),
+        }
+    }
+
+    fn size(&self) -> u64 {
+        match self {
+            Incoming::Path(path) => size(path),
+            Incoming::Content(size) => *size,
+        }
+    }
+}
+
+/// What happens at a destination path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// Nothing exists at the path
+    NoConflict,
+    /// The existing entry is replaced
+    Overwrite(OnConflict),
+    /// The existing entry is kept and nothing is written
+    Skip(OnConflict),
+    /// The entry is written to this free sibling path instead
+    Rename(Utf8PathBuf),
+}
+
+impl Resolution {
+    /// The path to write to, `None` if nothing is written.
+    pub fn destination<'a>(&'a self, path: &'a Utf8Path) -> Option<&'a Utf8Path> {
+        match self {
+            Resolution::NoConflict | Resolution::Overwrite(_) => Some(path),
+            Resolution::Skip(_) => None,
+            Resolution::Rename(renamed) => Some(renamed),
+        }
+    }
+
+    /// Names the strategy which decided, empty if there was no conflict or the user decided.
+    pub fn note(&self) -> String {
+        match self {
+            Resolution::NoConflict | Resolution::Overwrite(OnConflict::Ask) => String::new(),
+            Resolution::Overwrite(on_conflict) | Resolution::Skip(on_conflict) => {
+                format!(" (on conflict: {})", on_conflict.name())
+            }
+            Resolution::Rename(_) => " (on conflict: rename)".to_owned(),
+        }
+    }
+}
+
+/// Decides what happens when `incoming` would be written to the existing `path`.
+/// Renamed entries get a path which neither exists nor is in `reserved`, and which is added to it.
+/// Returns `None` for `OnConflict::Ask`, the user has to decide.
+pub fn resolve_conflict(
+    on_conflict: OnConflict,
+    incoming: &Incoming,
+    path: &Utf8Path,
+    reserved: &mut Vec<Utf8PathBuf>,
+) -> Option<Resolution> {
+    let overwrite = match on_conflict {
+        OnConflict::Ask => return None,
+        OnConflict::Skip => false,
+        OnConflict::Overwrite => true,
+        OnConflict::Rename => {
+            let renamed = free_sibling(path, reserved);
+            reserved.push(renamed.clone());
+            return Some(Resolution::Rename(renamed));
+        }
+        OnConflict::Newer => {
+            let existing = path.symlink_metadata().ok().and_then(|m| m.modified().ok());
+            incoming.modified() > existing
+        }
+        OnConflict::Larger => incoming.size() > size(path),
+    };
+    Some(if overwrite {
+        Resolution::Overwrite(on_conflict)
+    } else {
+        Resolution::Skip(on_conflict)
+    })
+}
+
+// The size of the contents of directories
+fn size(path: &Utf8Path) -> u64 {
+    match path.symlink_metadata() {
+        Ok(metadata) if metadata.is_dir() => summarize_tree(path).bytes,
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    }
+}
+
+/// The first of "name (1).ext", "name (2).ext", … which is free.
+fn free_sibling(path: &Utf8Path, reserved: &[Utf8PathBuf]) -> Utf8PathBuf {
+    let is_dir = path.is_dir();
+    let name = path.file_name().unwrap_or_default();
+    let (stem, extension) = match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(extension)) if !is_dir => (stem, format!(".{}", extension)),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|i| path.with_file_name(format!("{} ({}){}", stem, i, extension)))
+        .find(|sibling| sibling.symlink_metadata().is_err() && !reserved.contains(sibling))
+        .unwrap()
+}
@@ -0,0 +1,56 @@
+use camino::Utf8Path;
+
+use crate::destination::{confirm_conflicts, locate, plan_transfers};
+use crate::merge::{plan_merge, Transfer};
+use crate::{
+    execute, CopyOptions, Destination, Error, ExecuteOptions, OnConflict, Plan, Question, Report,
+    StandardOptions,
+};
+
+/// How `cop` copies, besides the `StandardOptions`.
+#[derive(Default)]
+pub struct CopOptions {
+    pub copy: CopyOptions,
+    pub on_conflict: OnConflict,
+    /// Copy the contents of directories into existing directories,
+    /// asking `Question::Overwrite` for each conflicting entry and skipping declined ones
+    pub merge: bool,
+    /// Compare the SHA-256 checksums of the copies to the originals
+    pub verify: bool,
+    /// With `verify`, write the checksums of the copied files to DESTINATION.sha256
+    pub manifest: bool,
+}
+
+/// Copies `sources` to `destination`, asking `confirm` before replacing existing entries.
+pub fn cop(
+    options: &StandardOptions,
+    cop_options: &CopOptions,
+    sources: &[&Utf8Path],
+    destination: Destination,
+    confirm: &mut dyn FnMut(&Question) -> bool,
+) -> Result<Report, Error> {
+    let mut plan = Plan::default();
+    let located = locate(&mut plan, sources, destination, options.keep_going)?;
+    if cop_options.merge {
+        plan_merge(&mut plan, Transfer::Copy, &located, confirm)?;
+        plan.validate(options.keep_going)?;
+    } else {
+        let ask = plan_transfers(
+            &mut plan,
+            Transfer::Copy,
+            cop_options.on_conflict,
+            None,
+            &located,
+        );
+        plan.validate(options.keep_going)?;
+        confirm_conflicts(destination, &ask, &[], confirm)?;
+    }
+    let execute_options = ExecuteOptions {
+        copy: cop_options.copy.clone(),
+        verify: cop_options.verify,
+        manifest: cop_options.manifest,
+        keep_going: options.keep_going,
+        ..Default::default()
+    };
+    Ok(execute(options, &execute_options, plan))
+}
@@ -0,0 +1,226 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+};
+
+// The size of the chunks copied at once
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// Whether copies share the data of the originals on copy-on-write
+/// file systems like btrfs and XFS, until either of them is changed.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Reflink {
+    /// Share the data when the file system supports it, copy it otherwise
+    #[default]
+    Auto,
+    /// Fail to copy files whose data cannot be shared
+    Always,
+    /// Always copy the data
+    Never,
+}
+
+/// Whether copies have holes, ranges of zeros which take up no disk space.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sparse {
+    /// Keep the holes of sparse originals
+    #[default]
+    Auto,
+    /// Keep the holes and also turn ranges of zeros into holes
+    Always,
+    /// Write zeros for the holes
+    Never,
+}
+
+/// Copies the contents of `from` to the empty file `to`,
+/// returns whether the data is shared via a reflink.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn copy_contents(
+    from: &File,
+    to: &File,
+    metadata: &std::fs::Metadata,
+    reflink: Reflink,
+    sparse: Sparse,
+) -> std::io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    if reflink != Reflink::Never {
+        match rustix::fs::ioctl_ficlone(to, from) {
+            Ok(()) => return Ok(true),
+            Err(error) if reflink == Reflink::Always => return Err(reflink_error(error.into())),
+            Err(_) => {}
+        }
+    }
+    let in_kernel = reflink != Reflink::Never;
+    let is_sparse = metadata.blocks() * 512 < metadata.len();
+    match sparse {
+        Sparse::Auto if is_sparse => {
+            for (start, end) in data_segments(from, metadata.len())? {
+                copy_range(from, to, start, end, in_kernel)?;
+            }
+            // Holes at the end are not created by writing
+            to.set_len(metadata.len())?;
+        }
+        Sparse::Always => {
+            for (start, end) in data_segments(from, metadata.len())? {
+                copy_range_skipping_zeros(from, to, start, end)?;
+            }
+            to.set_len(metadata.len())?;
+        }
+        Sparse::Auto => {
+            if !in_kernel || !copy_in_kernel(from, to)? {
+                copy_in_userspace(from, to)?;
+            }
+        }
+        // `copy_file_range` may keep holes, writing the zeros makes sure they are filled
+        Sparse::Never => copy_in_userspace(from, to)?,
+    }
+    Ok(false)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(crate) fn copy_contents(
+    from: &File,
+    to: &File,
+    _: &std::fs::Metadata,
+    reflink: Reflink,
+    _: Sparse,
+) -> std::io::Result<bool> {
+    if reflink == Reflink::Always {
+        return Err(reflink_error(std::io::ErrorKind::Unsupported.into()));
+    }
+    copy_in_userspace(from, to)?;
+    Ok(false)
+}
+
+fn reflink_error(error: std::io::Error) -> std::io::Error {
+    std::io::Error::new(error.kind(), format!("Cannot create a reflink: {}", error))
+}
+
+/// Copies with `copy_file_range`, which avoids moving the data through
+/// user space and lets network file systems copy on the server.
+/// Returns false if nothing was copied because the files don't support it.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn copy_in_kernel(from: &File, to: &File) -> std::io::Result<bool> {
+    let mut copied_any = false;
+    loop {
+        match rustix::fs::copy_file_range(from, None, to, None, CHUNK_SIZE) {
+            Ok(0) => return Ok(true),
+            Ok(_) => copied_any = true,
+            Err(error) if !copied_any && is_unsupported(error) => return Ok(false),
+            Err(error) => return Err(error.into()),
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn is_unsupported(error: rustix::io::Errno) -> bool {
+    use rustix::io::Errno;
+    matches!(
+        error,
+        Errno::NOSYS | Errno::XDEV | Errno::OPNOTSUPP | Errno::INVAL | Errno::PERM
+    )
+}
+
+// Not `std::io::copy`, which can use `copy_file_range` and thus share the data
+fn copy_in_userspace(mut from: &File, mut to: &File) -> std::io::Result<()> {
+    let mut buffer = vec![0; CHUNK_SIZE];
+    loop {
+        match from.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(length) => to.write_all(&buffer[..length])?,
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// The ranges of `file` which are not holes, the whole file
+/// if the file system cannot tell where the holes are.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn data_segments(file: &File, length: u64) -> std::io::Result<Vec<(u64, u64)>> {
+    use rustix::{fs::SeekFrom, io::Errno};
+
+    let mut segments = Vec::new();
+    let mut offset = 0;
+    while offset < length {
+        let start = match rustix::fs::seek(file, SeekFrom::Data(offset as i64)) {
+            Ok(start) => start,
+            // Only holes until the end
+            Err(Errno::NXIO) => break,
+            Err(Errno::INVAL) if offset == 0 => return Ok(vec![(0, length)]),
+            Err(error) => return Err(error.into()),
+        };
+        let end = rustix::fs::seek(file, SeekFrom::Hole(start as i64))?.min(length);
+        segments.push((start, end));
+        offset = end;
+    }
+    Ok(segments)
+}
+
+/// Copies the bytes from `start` to `end` to the same offsets.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn copy_range(
+    from: &File,
+    to: &File,
+    start: u64,
+    end: u64,
+    in_kernel: bool,
+) -> std::io::Result<()> {
+    use std::os::unix::fs::FileExt;
+
+    let mut offset = start;
+    if in_kernel {
+        while offset < end {
+            let (mut offset_in, mut offset_out) = (offset, offset);
+            let length = CHUNK_SIZE.min((end - offset) as usize);
+            match rustix::fs::copy_file_range(
+                from,
+                Some(&mut offset_in),
+                to,
+                Some(&mut offset_out),
+                length,
+            ) {
+                Ok(0) => break,
+                Ok(copied) => offset += copied as u64,
+                Err(error) if is_unsupported(error) => break,
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+    let mut buffer = vec![0; CHUNK_SIZE];
+    while offset < end {
+        let length = CHUNK_SIZE.min((end - offset) as usize);
+        let read = from.read_at(&mut buffer[..length], offset)?;
+        if read == 0 {
+            break;
+        }
+        to.write_all_at(&buffer[..read], offset)?;
+        offset += read as u64;
+    }
+    Ok(())
+}
+
+/// Like `copy_range` in user space, but leaves holes for chunks of zeros.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn copy_range_skipping_zeros(from: &File, to: &File, start: u64, end: u64) -> std::io::Result<()> {
+    use std::os::unix::fs::FileExt;
+
+    // Small enough to find the zeros of partly used blocks
+    const ZEROS_CHUNK_SIZE: usize = 4096;
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut offset = start;
+    while offset < end {
+        let length = CHUNK_SIZE.min((end - offset) as usize);
+        let read = from.read_at(&mut buffer[..length], offset)?;
+        if read == 0 {
+            break;
+        }
+        for (i, chunk) in buffer[..read].chunks(ZEROS_CHUNK_SIZE).enumerate() {
+            if chunk.iter().any(|&byte| byte != 0) {
+                to.write_all_at(chunk, offset + (i * ZEROS_CHUNK_SIZE) as u64)?;
+            }
+        }
+        offset += read as u64;
+    }
+    Ok(())
+}
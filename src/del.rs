@@ -0,0 +1,122 @@
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::{
+    absolute_path, execute, find_held_open, find_incoming_links, Error, ExecuteOptions, Operation,
+    Plan, Question, Report, StandardOptions,
+};
+
+/// How `del` deletes, besides the `StandardOptions`.
+#[derive(Default)]
+pub struct DelOptions {
+    /// Search this directory for symlinks pointing into the deleted paths,
+    /// which `Question::Delete` lists
+    pub check_links: Option<Utf8PathBuf>,
+    /// Find the running processes which hold the deleted paths open,
+    /// which `Question::Delete` lists
+    pub check_open: bool,
+    /// Stage the removals in the git index
+    pub git: bool,
+}
+
+/// Deletes the files and directories at `paths` once `confirm` agrees to `Question::Delete`.
+/// Paths which don't exist count as deleted, a path which fails to be deleted
+/// doesn't keep the others from being deleted.
+pub fn del(
+    options: &StandardOptions,
+    del_options: &DelOptions,
+    paths: &[&Utf8Path],
+    confirm: &mut dyn FnMut(&Question) -> bool,
+) -> Result<Report, Error> {
+    let mut plan = plan_deletion(paths);
+    plan.validate(options.keep_going)?;
+    let incoming_links = match &del_options.check_links {
+        Some(scope) => find_incoming_links(scope, paths),
+        None => Vec::new(),
+    };
+    let held_open = if del_options.check_open {
+        find_held_open(paths)
+    } else {
+        Vec::new()
+    };
+    if !confirm(&Question::Delete {
+        paths,
+        incoming_links: &incoming_links,
+        held_open: &held_open,
+    }) {
+        return Err(Error::Declined);
+    }
+    let execute_options = ExecuteOptions {
+        git: del_options.git,
+        keep_going: true,
+        ..Default::default()
+    };
+    Ok(execute(options, &execute_options, plan))
+}
+
+/// Plans to delete each existing path once, leaving out those inside other deleted paths.
+fn plan_deletion(paths: &[&Utf8Path]) -> Plan {
+    let existing: Vec<_> = paths
+        .iter()
+        .filter_map(|path| {
+            let metadata = path.symlink_metadata().ok()?;
+            Some((path, absolute_path(path), metadata.is_dir()))
+        })
+        .collect();
+    let mut plan = Plan::default();
+    for (i, (path, absolute, is_dir)) in existing.iter().enumerate() {
+        let covered = existing
+            .iter()
+            .enumerate()
+            .any(|(j, (_, other, _))| absolute.starts_with(other) && (absolute != other || j < i));
+        if !covered {
+            plan.push(Operation::Delete {
+                path: path.to_path_buf(),
+                is_dir: *is_dir,
+            });
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_del_asks_callback() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        let file = root.join("file");
+        std::fs::write(&file, "file").unwrap();
+        let paths = [file.as_path()];
+        let mut asked = Vec::new();
+        let declined = del(
+            &StandardOptions::quiet(),
+            &DelOptions::default(),
+            &paths,
+            &mut |question| {
+                if let Question::Delete { paths, .. } = question {
+                    asked.extend(paths.iter().map(|path| path.to_path_buf()));
+                }
+                false
+            },
+        );
+        assert!(matches!(declined, Err(Error::Declined)));
+        assert_eq!(asked, [file.as_path()]);
+        assert!(file.exists());
+
+        let report = del(
+            &StandardOptions::quiet(),
+            &DelOptions::default(),
+            &paths,
+            &mut |_| true,
+        )
+        .unwrap();
+        assert_eq!(report.done.len(), 1);
+        assert!(matches!(
+            report.done[0].step.operation,
+            Operation::Delete { is_dir: false, .. }
+        ));
+        assert!(!file.exists());
+    }
+}
@@ -0,0 +1,170 @@
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::merge::Transfer;
+use crate::{
+    directory_path, resolve_conflict, Backup, Error, HeldOpen, Incoming, KeepReason, OnConflict,
+    Operation, Plan, Question, Resolution, WalkError,
+};
+
+/// Where `mov` and `cop` put their sources.
+#[derive(Debug, Clone, Copy)]
+pub enum Destination<'a> {
+    /// Into this directory, keeping the names of the sources
+    Into(&'a Utf8Path),
+    /// At this path, for a single source
+    To(&'a Utf8Path),
+}
+
+/// The sources which are not at the destination yet, with the paths they would be written to.
+pub(crate) struct Located<'a> {
+    pub sources: Vec<&'a Utf8Path>,
+    pub paths: Vec<Utf8PathBuf>,
+}
+
+/// Checks that the sources exist and finds the paths they would be written to.
+/// Sources which are already located at the destination are kept in `plan`,
+/// missing ones are left out with `keep_going` and their errors added to `plan.failed`.
+pub(crate) fn locate<'a>(
+    plan: &mut Plan,
+    sources: &[&'a Utf8Path],
+    destination: Destination,
+    keep_going: bool,
+) -> Result<Located<'a>, Error> {
+    if matches!(destination, Destination::To(_)) && sources.len() != 1 {
+        return Err(Error::Invalid(format!(
+            "Expected 1 SOURCE_PATH argument, but got {}",
+            sources.len()
+        )));
+    }
+    let mut existing = Vec::new();
+    for source in sources {
+        match source.canonicalize_utf8() {
+            Ok(canonical_source) => existing.push((*source, canonical_source)),
+            Err(error) if keep_going => plan.failed.push(WalkError {
+                path: source.to_path_buf(),
+                error,
+            }),
+            Err(error) => return Err(Error::for_path(source, error)),
+        }
+    }
+    let mut located = Located {
+        sources: Vec::new(),
+        paths: Vec::new(),
+    };
+    let canonical_destination = match destination {
+        Destination::Into(destination) | Destination::To(destination) => {
+            destination.canonicalize_utf8().ok()
+        }
+    };
+    for (source, canonical_source) in existing {
+        match destination {
+            Destination::Into(destination) => {
+                if canonical_destination.is_some()
+                    && canonical_source.parent() == canonical_destination.as_deref()
+                {
+                    keep_located(plan, source, directory_path(destination));
+                } else {
+                    located.sources.push(source);
+                    located
+                        .paths
+                        .push(destination.join(source.file_name().unwrap()));
+                }
+            }
+            Destination::To(destination) => {
+                if canonical_destination.as_ref() == Some(&canonical_source) {
+                    keep_located(plan, source, destination.to_owned());
+                } else {
+                    located.sources.push(source);
+                    located.paths.push(destination.to_owned());
+                }
+            }
+        }
+    }
+    Ok(located)
+}
+
+fn keep_located(plan: &mut Plan, source: &Utf8Path, destination: Utf8PathBuf) {
+    plan.push(Operation::Keep {
+        path: destination,
+        reason: KeepReason::AlreadyLocated {
+            source: source.to_owned(),
+        },
+    });
+}
+
+/// Plans to write the located sources to their paths, applying `on_conflict` to the existing
+/// ones, which are deleted first or moved to their `backup`. Returns the paths of the conflicts
+/// which are left for the user to decide, see `confirm_conflicts`.
+pub(crate) fn plan_transfers(
+    plan: &mut Plan,
+    transfer: Transfer,
+    on_conflict: OnConflict,
+    backup: Option<&Backup>,
+    located: &Located,
+) -> Vec<Utf8PathBuf> {
+    let mut reserved = located.paths.clone();
+    let mut ask = Vec::new();
+    for (source, path) in located.sources.iter().zip(&located.paths) {
+        // A symlink is a conflict even when it is broken, it would be replaced as well
+        let existing = path.symlink_metadata().ok();
+        let resolution = if existing.is_some() {
+            resolve_conflict(on_conflict, &Incoming::Path(source), path, &mut reserved)
+                .unwrap_or_else(|| {
+                    ask.push(path.clone());
+                    Resolution::Overwrite(OnConflict::Ask)
+                })
+        } else {
+            Resolution::NoConflict
+        };
+        let Some(target) = resolution.destination(path).map(Utf8Path::to_owned) else {
+            plan.push_resolved(
+                Operation::Keep {
+                    path: path.clone(),
+                    reason: KeepReason::Skipped {
+                        source: Some(source.to_path_buf()),
+                    },
+                },
+                resolution,
+            );
+            continue;
+        };
+        if let Some(existing) = existing.filter(|_| target == *path) {
+            let (path, is_dir) = (path.clone(), existing.is_dir());
+            let operation = match backup {
+                Some(backup) => Operation::Backup {
+                    backup: backup.path_for(&path, &mut reserved),
+                    path,
+                    is_dir,
+                },
+                None => Operation::Delete { path, is_dir },
+            };
+            plan.push(operation);
+        }
+        plan.push_resolved(transfer.operation(source, &target), resolution);
+    }
+    ask
+}
+
+/// Asks `Question::Replace` for the conflict of a `Destination::To`,
+/// and `Question::Overwrite` once for all conflicts otherwise, listing the processes
+/// which hold moved paths open. Without conflicts, asks `Question::MoveHeldOpen` if there are any.
+pub(crate) fn confirm_conflicts(
+    destination: Destination,
+    ask: &[Utf8PathBuf],
+    held_open: &[HeldOpen],
+    confirm: &mut dyn FnMut(&Question) -> bool,
+) -> Result<(), Error> {
+    let confirmed = match (destination, ask) {
+        (_, []) if held_open.is_empty() => true,
+        (_, []) => confirm(&Question::MoveHeldOpen { held_open }),
+        (Destination::To(_), [path]) => confirm(&Question::Replace { path, held_open }),
+        _ => confirm(&Question::Overwrite {
+            paths: ask,
+            held_open,
+        }),
+    };
+    if !confirmed {
+        return Err(Error::Declined);
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use crate::{WalkError, FAILURE};
+
+/// Why `new`, `mov`, `cop` or `del` stopped.
+#[derive(Debug)]
+pub enum Error {
+    /// A confirmation was declined, nothing was changed
+    Declined,
+    /// The arguments cannot be carried out, nothing was changed
+    Invalid(String),
+    /// Errors for paths, changes made before them are kept
+    Paths(Vec<WalkError>),
+}
+
+impl Error {
+    pub(crate) fn for_path(path: &camino::Utf8Path, error: std::io::Error) -> Error {
+        Error::Paths(vec![WalkError {
+            path: path.to_owned(),
+            error,
+        }])
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Declined => write!(formatter, "Declined"),
+            Error::Invalid(message) => write!(formatter, "Error: {}", message),
+            Error::Paths(errors) => {
+                let lines: Vec<_> = errors.iter().map(WalkError::to_string).collect();
+                write!(formatter, "{}", lines.join("\n"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Vec<WalkError>> for Error {
+    fn from(errors: Vec<WalkError>) -> Error {
+        Error::Paths(errors)
+    }
+}
+
+/// Prints the error and exits, a declined confirmation exits silently.
+pub fn exit_on_error<T>(result: Result<T, Error>) -> T {
+    result.unwrap_or_else(|error| {
+        if !matches!(error, Error::Declined) {
+            eprintln!("{}", error);
+        }
+        std::process::exit(FAILURE);
+    })
+}
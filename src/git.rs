@@ -0,0 +1,365 @@
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Instant,
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::{absolute_path, walk, Entry, Visitor};
+
+/// Whether to update the git index for a change at `path`, because of `--git` or because
+/// the repository containing it sets `human-utils.git` to true. Git is only asked
+/// for paths inside of a work tree, once for each.
+pub fn git_enabled(flag: bool, path: &Utf8Path) -> bool {
+    static CONFIGURED: Mutex<BTreeMap<Utf8PathBuf, bool>> = Mutex::new(BTreeMap::new());
+    if flag {
+        return true;
+    }
+    let Some(work_tree) = work_tree(path) else {
+        return false;
+    };
+    *CONFIGURED
+        .lock()
+        .unwrap()
+        .entry(work_tree)
+        .or_insert_with_key(|work_tree| {
+            run(git(work_tree).args(["config", "--type=bool", "--get", "human-utils.git"]))
+                .is_ok_and(|output| output.trim() == "true")
+        })
+}
+
+// The closest directory above `path` containing ".git", found without running git
+fn work_tree(path: &Utf8Path) -> Option<Utf8PathBuf> {
+    absolute_path(path)
+        .parent()?
+        .ancestors()
+        .find(|directory| directory.join(".git").exists())
+        .map(Utf8Path::to_owned)
+}
+
+/// A path inside of a git work tree.
+struct Located {
+    root: Utf8PathBuf,
+    /// Relative to `root`
+    relative: Utf8PathBuf,
+}
+
+impl Located {
+    /// The path as a pathspec which does not match anything else.
+    fn pathspec(&self) -> String {
+        format!(":(literal){}", self.relative)
+    }
+}
+
+// `None` outside of work trees and for the root of one
+fn locate(path: &Utf8Path) -> Option<Located> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_str().is_empty() => parent,
+        _ => Utf8Path::new("."),
+    };
+    let parent = parent.canonicalize_utf8().ok()?;
+    let root = run(git(&parent).args(["rev-parse", "--show-toplevel"])).ok()?;
+    let root = Utf8PathBuf::from(root.trim_end_matches('\n'));
+    let relative = parent
+        .join(path.file_name()?)
+        .strip_prefix(&root)
+        .ok()?
+        .to_owned();
+    Some(Located { root, relative })
+}
+
+/// The entries in the git index for a file or directory, read before it is changed.
+pub struct Tracked {
+    located: Located,
+    /// The mode, object and path relative to the root of each entry
+    entries: Vec<(String, String, Utf8PathBuf)>,
+}
+
+/// The index entries at or under `path`, `None` if it is not tracked by git.
+pub fn tracked_entries(path: &Utf8Path) -> Option<Tracked> {
+    let located = locate(path)?;
+    let output =
+        run(git(&located.root).args(["ls-files", "--stage", "-z", "--", &located.pathspec()]))
+            .ok()?;
+    let entries: Vec<_> = output
+        .split_terminator('\0')
+        .filter_map(|line| {
+            let (info, path) = line.split_once('\t')?;
+            let mut info = info.split(' ');
+            let (mode, object) = (info.next()?, info.next()?);
+            Some((mode.to_owned(), object.to_owned(), Utf8PathBuf::from(path)))
+        })
+        .collect();
+    if entries.is_empty() {
+        return None;
+    }
+    Some(Tracked { located, entries })
+}
+
+impl Tracked {
+    /// Stages the removal of all entries, like `git rm --cached`.
+    pub fn stage_removal(&self) -> std::io::Result<()> {
+        let removals = self.removals();
+        update_index(&self.located.root, &removals)
+    }
+
+    /// Moves the entries to `to` in the index, keeping their staged contents like `git mv`,
+    /// after the files were moved. Outside of the repository of `from`,
+    /// the moved files are added to the repository of `to` if there is one.
+    pub fn stage_move(&self, to: &Utf8Path) -> std::io::Result<()> {
+        let Some(destination) = locate(to) else {
+            return self.stage_removal();
+        };
+        if destination.root != self.located.root {
+            self.stage_removal()?;
+            return stage_new(to).map(|_| ());
+        }
+        let mut records = self.removals();
+        for (mode, object, path) in &self.entries {
+            let inside = path.strip_prefix(&self.located.relative).unwrap();
+            let moved = if inside.as_str().is_empty() {
+                destination.relative.clone()
+            } else {
+                destination.relative.join(inside)
+            };
+            records.push(format!("{} {}\t{}", mode, object, moved));
+        }
+        update_index(&self.located.root, &records)
+    }
+
+    /// Adds the entries back to the index as they were, after their removal was staged.
+    pub fn stage_restore(&self) -> std::io::Result<()> {
+        let records: Vec<_> = self
+            .entries
+            .iter()
+            .map(|(mode, object, path)| format!("{} {}\t{}", mode, object, path))
+            .collect();
+        update_index(&self.located.root, &records)
+    }
+
+    fn removals(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|(_, object, path)| format!("0 {}\t{}", "0".repeat(object.len()), path))
+            .collect()
+    }
+}
+
+/// Adds the new file at `path` to the index, like `git add`,
+/// returns false outside of work trees and for ignored files.
+pub fn stage_new(path: &Utf8Path) -> std::io::Result<bool> {
+    let Some(located) = locate(path) else {
+        return Ok(false);
+    };
+    // Takes paths, not pathspecs
+    let ignored = git(&located.root)
+        .args(["check-ignore", "--quiet", "--", located.relative.as_str()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?
+        .success();
+    if ignored {
+        return Ok(false);
+    }
+    run(git(&located.root).args(["add", "--", &located.pathspec()]))?;
+    Ok(true)
+}
+
+/// Work in a git repository which only exists locally and would be lost with it.
+pub struct UnsavedWork {
+    pub repository: Utf8PathBuf,
+    pub modified_files: usize,
+    pub untracked_files: usize,
+    pub stashes: usize,
+    /// Commits which are not on any remote
+    pub unpushed_commits: usize,
+}
+
+impl UnsavedWork {
+    /// Lists the kinds of work, like "2 modified files, 1 stash".
+    pub fn describe(&self) -> String {
+        [
+            (self.modified_files, "modified file"),
+            (self.untracked_files, "untracked file"),
+            (self.stashes, "stash"),
+            (self.unpushed_commits, "unpushed commit"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, kind)| format!("{} {}{}", count, kind, if count == 1 { "" } else { "s" }))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+/// The repositories with unsaved work found at or under a path.
+#[derive(Default)]
+pub struct FoundUnsavedWork {
+    pub work: Vec<UnsavedWork>,
+    /// Whether the search stopped at its deadline, so that repositories may be missing
+    pub truncated: bool,
+}
+
+struct FindingRepositories {
+    repositories: Mutex<Vec<Utf8PathBuf>>,
+    deadline: Instant,
+    truncated: AtomicBool,
+}
+
+impl Visitor for FindingRepositories {
+    type Directory = ();
+
+    fn skip(&self, entry: &Entry) -> bool {
+        if Instant::now() >= self.deadline {
+            self.truncated.store(true, Ordering::Relaxed);
+            return true;
+        }
+        if entry.name != ".git" {
+            return false;
+        }
+        let repository = entry.path.parent().unwrap().to_owned();
+        self.repositories.lock().unwrap().push(repository);
+        true
+    }
+
+    fn enter(&self, _: Option<&()>, _: &Entry) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn visit(&self, _: Option<&()>, _: &Entry) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn leave(&self, _: Option<&()>, _: &Entry, _: &(), _: bool) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The git work trees at or under the directory `path` which contain unsaved work.
+/// Searching for them stops at `deadline`, the work trees found until then are checked.
+/// Repositories which git cannot read are left out.
+pub fn find_unsaved_work(path: &Utf8Path, deadline: Instant) -> FoundUnsavedWork {
+    if !path
+        .symlink_metadata()
+        .is_ok_and(|metadata| metadata.is_dir())
+    {
+        return FoundUnsavedWork::default();
+    }
+    let finding = FindingRepositories {
+        repositories: Mutex::default(),
+        deadline,
+        truncated: AtomicBool::new(false),
+    };
+    walk(path, &finding);
+    let mut repositories = finding.repositories.into_inner().unwrap();
+    repositories.sort();
+    let work = repositories
+        .iter()
+        .filter_map(|repository| unsaved_work(repository))
+        .filter(|work| !work.describe().is_empty())
+        .collect();
+    FoundUnsavedWork {
+        work,
+        truncated: finding.truncated.into_inner(),
+    }
+}
+
+fn unsaved_work(repository: &Utf8Path) -> Option<UnsavedWork> {
+    let status =
+        run(git(repository).args(["status", "--porcelain", "-z", "--untracked-files=all"])).ok()?;
+    let mut modified_files = 0;
+    let mut untracked_files = 0;
+    let mut entries = status.split_terminator('\0');
+    while let Some(entry) = entries.next() {
+        if entry.starts_with("??") {
+            untracked_files += 1;
+        } else {
+            modified_files += 1;
+            // Renames are followed by the original path
+            if entry.starts_with('R') || entry.starts_with('C') {
+                entries.next();
+            }
+        }
+    }
+    let stashes = run(git(repository).args(["stash", "list"]))
+        .ok()?
+        .lines()
+        .count();
+    let unpushed_commits =
+        run(git(repository).args(["rev-list", "--count", "--branches", "--not", "--remotes"]))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()?;
+    Some(UnsavedWork {
+        repository: repository.to_owned(),
+        modified_files,
+        untracked_files,
+        stashes,
+        unpushed_commits,
+    })
+}
+
+fn update_index(root: &Utf8Path, records: &Vec<String>) -> std::io::Result<()> {
+    let mut child = git(root)
+        .args(["update-index", "-z", "--index-info"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    for record in records {
+        stdin.write_all(record.as_bytes())?;
+        stdin.write_all(b"\0")?;
+    }
+    drop(stdin);
+    let output = child.wait_with_output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(git_error(&output.stderr))
+    }
+}
+
+fn git(directory: &Utf8Path) -> Command {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(directory);
+    command
+}
+
+fn run(command: &mut Command) -> std::io::Result<String> {
+    let output = command.stdin(Stdio::null()).output()?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(git_error(&output.stderr))
+    }
+}
+
+fn git_error(stderr: &[u8]) -> std::io::Error {
+    let message = String::from_utf8_lossy(stderr);
+    std::io::Error::other(format!("git failed: {}", message.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_unsaved_work_stops_at_deadline() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        std::fs::create_dir_all(root.join("repo/.git")).unwrap();
+        let expired = find_unsaved_work(root, Instant::now());
+        assert!(expired.truncated);
+        assert!(expired.work.is_empty());
+        let far = Instant::now() + std::time::Duration::from_secs(60);
+        assert!(!find_unsaved_work(&root.join("repo/.git"), far).truncated);
+    }
+}
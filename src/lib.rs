@@ -1,8 +1,65 @@
+mod backup;
+mod conflict;
+mod cop;
+mod copy_file;
+mod del;
+mod destination;
+mod error;
+mod git;
 mod lazy_path;
-
+mod links;
+mod merge;
+mod mov;
+mod new;
+mod open_files;
+mod permissions;
+mod plan;
+mod preserve;
+mod progress;
+mod prompt;
+mod question;
+mod summary;
+mod tree_preview;
+mod verify;
+mod walk;
+
+pub use backup::{Backup, BackupNaming};
+pub use conflict::{resolve_conflict, Incoming, OnConflict, Resolution};
+pub use cop::{cop, CopOptions};
+pub use copy_file::{Reflink, Sparse};
+pub use del::{del, DelOptions};
+pub use destination::Destination;
+pub use error::{exit_on_error, Error};
+pub use git::{
+    find_unsaved_work, git_enabled, stage_new, tracked_entries, FoundUnsavedWork, Tracked,
+    UnsavedWork,
+};
 pub use lazy_path::LazyPath;
-
-use std::{borrow::Cow, io::Write};
+pub use links::{
+    default_link_scope, find_incoming_links, find_links_to_fix, rewrite_link, IncomingLink, LinkFix,
+};
+pub use mov::{mov, MovOptions};
+pub use new::{new, NewOptions};
+pub use open_files::{find_held_open, HeldOpen, Holding};
+pub use plan::{
+    execute, print_report, Done, ExecuteOptions, KeepReason, Merged, Operation, Plan, Report,
+    SetAside, Step,
+};
+pub use preserve::{Attribute, Preserve};
+pub use progress::Progress;
+pub use prompt::{confirm, confirm_or_exit, confirm_word, confirm_word_or_exit, DefaultAnswer};
+pub use question::{ask_in_terminal, Question};
+pub use summary::{
+    describe_path, print_path_summary, summarize_for_prompt, summary_deadline, Description,
+};
+pub use tree_preview::TreePreview;
+pub use verify::{hash_tree, move_tree_verified, verify_tree, write_manifest, Checksum};
+pub use walk::{
+    copy_tree, move_tree, remove_tree, summarize_tree, summarize_tree_until, walk, CopyOptions,
+    CopySummary, Dir, Entry, TreeSummary, Visitor, WalkError,
+};
+
+use std::borrow::Cow;
 
 use camino::{Utf8Path, Utf8PathBuf};
 use colored::{ColoredString, Colorize};
@@ -15,33 +72,7 @@ pub fn set_color_override(options: &StandardOptions) {
     }
 }
 
-pub fn find_existing_or_ancestor_for_print<'a>(
-    options: &StandardOptions,
-    path: &'a Utf8Path,
-) -> Option<&'a Utf8Path> {
-    if options.no_color {
-        return None;
-    }
-
-    if path.exists() {
-        return Some(path);
-    }
-
-    find_existing_ancestor_directory(path)
-}
-
-pub fn find_existing_ancestor_directory_for_print<'a>(
-    options: &StandardOptions,
-    path: &'a Utf8Path,
-) -> Option<&'a Utf8Path> {
-    if options.no_color {
-        return None;
-    }
-
-    find_existing_ancestor_directory(path)
-}
-
-pub fn find_existing_ancestor_directory<'a>(path: &'a Utf8Path) -> Option<&'a Utf8Path> {
+pub fn find_existing_ancestor_directory(path: &Utf8Path) -> Option<&Utf8Path> {
     let mut ancestor = path.parent();
     while let Some(ancestor_path) = ancestor {
         if ancestor_path.exists() {
@@ -56,7 +87,7 @@ pub fn get_cwd() -> std::path::PathBuf {
     std::env::current_dir().unwrap()
 }
 
-pub fn handle_cwd<'a, 'b>(path: &'a Utf8Path) -> Cow<'a, Utf8Path> {
+pub fn handle_cwd<'a>(path: &'a Utf8Path) -> Cow<'a, Utf8Path> {
     let path = normalize_path(path);
 
     if path.as_str() == "." || path.as_str() == ".." {
@@ -66,7 +97,7 @@ pub fn handle_cwd<'a, 'b>(path: &'a Utf8Path) -> Cow<'a, Utf8Path> {
     }
 }
 
-fn normalize_path(path: &Utf8Path) -> Cow<Utf8Path> {
+fn normalize_path(path: &Utf8Path) -> Cow<'_, Utf8Path> {
     let mut new_components = Vec::new();
 
     let mut num_old_components = 0;
@@ -110,110 +141,41 @@ fn normalize_path(path: &Utf8Path) -> Cow<Utf8Path> {
     }
 }
 
-pub fn create_directory(options: &StandardOptions, path: &Utf8Path) {
-    if options.dry_run {
-        return;
-    }
-    std::fs::create_dir_all(path).unwrap();
-}
-
-pub fn create_file(options: &StandardOptions, path: &Utf8Path, content: Option<&str>) {
-    if options.dry_run {
-        return;
-    }
-    create_parent_directory(options, path);
-    if let Some(text) = content {
-        std::fs::write(path, text).unwrap();
-    } else {
-        std::fs::File::create(path).unwrap();
-    }
-}
-
-pub fn create_parent_directory<'a>(options: &StandardOptions, path: &'a Utf8Path) -> &'a Utf8Path {
-    let parent_directory = path.parent().unwrap();
-    if !options.dry_run {
-        std::fs::create_dir_all(parent_directory).unwrap();
-    }
-    parent_directory
-}
-
-pub fn check_path_exists_and_confirm_or_exit(options: &StandardOptions, path: &Utf8Path) {
-    if options.force {
-        return;
-    }
-
-    if let Ok(metadata) = path.symlink_metadata() {
-        let file_type = if metadata.is_dir() {
-            "Directory"
-        } else {
-            "File"
-        };
-        print!(
-            "{} \"{}\" already exists, replace it? [Y/n]",
-            file_type, path
-        );
-        confirm_or_exit();
-    }
-}
-
-pub fn check_paths_exist_and_confirm_or_exit(options: &StandardOptions, paths: &Vec<Utf8PathBuf>) {
-    if options.force {
-        return;
-    }
-    let clashing: Vec<_> = paths.iter().filter(|path| path.exists()).collect();
-    ask_to_overwrite(&clashing);
-}
-
-pub fn ask_to_overwrite<P: AsRef<Utf8Path>>(clashing: &Vec<P>) {
-    if clashing.is_empty() {
-        return;
-    }
-    if let [path] = clashing.as_slice() {
-        ask_for_single_path(path.as_ref());
-    } else {
-        ask_for_multiple_paths(&clashing);
-    }
-    confirm_or_exit();
-}
-
-pub fn confirm_or_exit() {
-    std::io::stdout().flush().unwrap();
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input).unwrap();
-    if input != "\n" && !input.trim().to_lowercase().starts_with('y') {
-        std::process::exit(FAILURE);
+/// Joins `path` to the current working directory and removes `.` and `..` components,
+/// without resolving symlinks.
+pub fn absolute_path(path: &Utf8Path) -> Utf8PathBuf {
+    let cwd = get_cwd();
+    let cwd = Utf8Path::from_path(&cwd).unwrap();
+    let mut absolute = Utf8PathBuf::new();
+    for component in cwd.join(path).components() {
+        match component {
+            camino::Utf8Component::CurDir => {}
+            camino::Utf8Component::ParentDir => {
+                absolute.pop();
+            }
+            component => absolute.push(component),
+        }
     }
+    absolute
 }
 
-fn ask_for_single_path(path: &Utf8Path) {
-    let metadata = path.symlink_metadata().unwrap();
-    let file_type = if metadata.is_dir() {
-        "directory"
-    } else {
-        "file"
-    };
-    print!("Overwrite {} \"{}\"? [Y/n]", file_type, path);
-}
-
-pub fn ask_for_multiple_paths<P: AsRef<Utf8Path>>(paths: &Vec<P>) {
-    println!("For the following...");
-    for path in paths {
-        let metadata = path.as_ref().symlink_metadata().unwrap();
-        print_path(path.as_ref(), &metadata);
-    }
-    print!("...overwrite all? [Y/n]");
-}
-
-fn print_path(path: &Utf8Path, metadata: &std::fs::Metadata) {
-    println!("{}", format_path(path, metadata));
-}
-
-pub fn format_path(path: &Utf8Path, metadata: &std::fs::Metadata) -> String {
-    if metadata.is_dir() {
-        directory_path(path).to_string()
-    } else {
-        path_string(path)
+/// The path to `to` relative to the directory `from`, both paths must be absolute.
+pub fn relative_path(from: &Utf8Path, to: &Utf8Path) -> Utf8PathBuf {
+    let common = from
+        .components()
+        .zip(to.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative: Utf8PathBuf = from
+        .components()
+        .skip(common)
+        .map(|_| camino::Utf8Component::ParentDir)
+        .collect();
+    relative.extend(to.components().skip(common));
+    if relative.as_str().is_empty() {
+        relative.push(".");
     }
+    relative
 }
 
 pub fn color_new(
@@ -234,7 +196,7 @@ pub fn color_new(
             )
             .normal()
         } else {
-            path_string(path).color(color)
+            path.as_str().color(color)
         },
         color,
     )
@@ -270,10 +232,7 @@ pub fn directory_path(path: &Utf8Path) -> Utf8PathBuf {
     Utf8PathBuf::from(format!("{}{}", path, std::path::MAIN_SEPARATOR))
 }
 
-pub fn path_string<S: AsRef<str>>(path: S) -> String
-where
-    S: AsRef<str>,
-{
+pub fn path_string<S: AsRef<str>>(path: S) -> String {
     let path = path.as_ref();
     if path.contains(' ') {
         format!("\"{}\"", path)
@@ -282,6 +241,21 @@ where
     }
 }
 
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
 #[macro_export]
 macro_rules! message_success {
     ($options:ident, $($arg:tt)*) => {
@@ -293,6 +267,8 @@ macro_rules! message_success {
 
 pub const SUCCESS: i32 = 0;
 pub const FAILURE: i32 = 1;
+/// Exit code when some paths failed with `--keep-going` and others succeeded
+pub const PARTIAL_FAILURE: i32 = 2;
 
 #[derive(clap::Args, Debug, Clone)]
 pub struct StandardOptions {
@@ -311,6 +287,10 @@ pub struct StandardOptions {
     // #[tested(nam_dry_run)]
     pub dry_run: bool,
 
+    /// With --dry-run, print the affected file tree before and after the changes
+    #[arg(long, requires = "dry_run")]
+    pub tree: bool,
+
     /// Always color output
     #[arg(long)]
     pub color: bool,
@@ -318,6 +298,38 @@ pub struct StandardOptions {
     /// Never color output
     #[arg(long, conflicts_with = "color")]
     pub no_color: bool,
+
+    /// Highlight entries modified within the last MINUTES in prompts,
+    /// and ask to type out the confirmation when there are any, 0 turns this off
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        env = "HUMAN_UTILS_RECENT",
+        default_value_t = 60
+    )]
+    pub recent: u64,
+
+    /// Carry on with the remaining paths after one fails, print a summary at the end
+    /// and exit with code 2 if only some of them failed
+    #[arg(long)]
+    pub keep_going: bool,
+}
+
+#[cfg(test)]
+impl StandardOptions {
+    /// Options which neither print nor color anything, for the unit tests.
+    pub(crate) fn quiet() -> Self {
+        StandardOptions {
+            force: false,
+            silent: true,
+            dry_run: false,
+            tree: false,
+            color: false,
+            no_color: true,
+            recent: 0,
+            keep_going: false,
+        }
+    }
 }
 
 // a unit test
@@ -331,4 +343,38 @@ mod tests {
         let prefix = Utf8PathBuf::from("/a");
         assert_eq!(strip_path_prefix(&path, &prefix), "b/c");
     }
+
+    #[test]
+    fn test_relative_path() {
+        let relative = |from, to| relative_path(Utf8Path::new(from), Utf8Path::new(to));
+        assert_eq!(relative("/a/b", "/a/c/d"), "../c/d");
+        assert_eq!(relative("/a", "/a/b"), "b");
+        assert_eq!(relative("/a/b", "/a"), "..");
+        assert_eq!(relative("/a", "/a"), ".");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(999), "999 B");
+        assert_eq!(format_bytes(1_500), "1.5 KB");
+        assert_eq!(format_bytes(40_000_000_000), "40.0 GB");
+    }
+
+    #[test]
+    fn test_recent_defaults_to_an_hour() {
+        #[derive(clap::Parser)]
+        struct Arguments {
+            #[command(flatten)]
+            options: StandardOptions,
+        }
+        let parse = |args: &[&str]| {
+            <Arguments as clap::Parser>::parse_from(
+                std::iter::once("test").chain(args.iter().copied()),
+            )
+            .options
+            .recent
+        };
+        assert_eq!(parse(&[]), 60);
+        assert_eq!(parse(&["--recent=0"]), 0);
+    }
 }
@@ -0,0 +1,230 @@
+use std::sync::Mutex;
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::{absolute_path, relative_path, Dir, Entry, Visitor};
+
+/// A relative symlink whose target has to change
+/// for it to keep pointing at the same path after a move.
+pub struct LinkFix {
+    /// The location of the link before the move
+    pub link: Utf8PathBuf,
+    /// The location of the link after the move
+    pub moved_link: Utf8PathBuf,
+    pub target: Utf8PathBuf,
+    pub new_target: Utf8PathBuf,
+}
+
+/// A symlink outside of the affected paths which resolves to one of them,
+/// or to an entry inside of them.
+pub struct IncomingLink {
+    pub link: Utf8PathBuf,
+    pub target: Utf8PathBuf,
+    resolved_target: Utf8PathBuf,
+}
+
+impl IncomingLink {
+    /// The fix which keeps the link pointing at the same entry after moving `from` to `to`,
+    /// `None` if the link does not point at or inside `from`.
+    pub fn retarget(&self, from: &Utf8Path, to: &Utf8Path) -> Option<LinkFix> {
+        let relative_target = self
+            .resolved_target
+            .strip_prefix(absolute_path(from))
+            .ok()?;
+        let absolute_new_target = absolute_path(to).join(relative_target);
+        let new_target = if self.target.is_absolute() {
+            absolute_new_target
+        } else {
+            relative_path(absolute_path(&self.link).parent()?, &absolute_new_target)
+        };
+        Some(LinkFix {
+            link: self.link.clone(),
+            moved_link: self.link.clone(),
+            target: self.target.clone(),
+            new_target,
+        })
+    }
+}
+
+#[derive(Default)]
+struct CollectingSymlinks {
+    // Absolute paths which are not walked
+    skipped: Vec<Utf8PathBuf>,
+    symlinks: Mutex<Vec<(Utf8PathBuf, Utf8PathBuf)>>,
+}
+
+impl Visitor for CollectingSymlinks {
+    type Directory = ();
+
+    fn skip(&self, entry: &Entry) -> bool {
+        entry.name == ".git" || self.skipped.contains(&absolute_path(&entry.path))
+    }
+
+    fn enter(&self, _: Option<&()>, _: &Entry) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn visit(&self, _: Option<&()>, entry: &Entry) -> std::io::Result<()> {
+        if entry.metadata.is_symlink() {
+            // Targets which are not UTF-8 are left alone
+            if let Ok(target) = Utf8PathBuf::from_path_buf(entry.parent.read_link(entry.name)?) {
+                let mut symlinks = self.symlinks.lock().unwrap();
+                symlinks.push((entry.path.clone(), target));
+            }
+        }
+        Ok(())
+    }
+
+    fn leave(&self, _: Option<&()>, _: &Entry, _: &(), _: bool) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Finds the relative symlinks at or inside `from` which would no longer resolve
+/// after moving `from` to `to`. Links pointing inside `from` move along with their targets.
+/// Directories are only searched with `search_directories`, otherwise only a symlink
+/// at `from` itself is checked.
+pub fn find_links_to_fix(from: &Utf8Path, to: &Utf8Path, search_directories: bool) -> Vec<LinkFix> {
+    if !search_directories
+        && !from
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.is_symlink())
+    {
+        return Vec::new();
+    }
+    let collecting = CollectingSymlinks::default();
+    crate::walk(from, &collecting);
+    let absolute_from = absolute_path(from);
+    let absolute_to = absolute_path(to);
+    collecting
+        .symlinks
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .filter_map(|(link, target)| {
+            if target.is_absolute() {
+                return None;
+            }
+            let absolute_link = absolute_path(&link);
+            let resolved_target = absolute_path(&absolute_link.parent()?.join(&target));
+            let is_root = absolute_link == absolute_from;
+            if !is_root && resolved_target.starts_with(&absolute_from) {
+                return None;
+            }
+            let relative_link = absolute_link.strip_prefix(&absolute_from).unwrap();
+            let (moved_link, absolute_moved_link) = if is_root {
+                (to.to_owned(), absolute_to.clone())
+            } else {
+                (to.join(relative_link), absolute_to.join(relative_link))
+            };
+            let new_target = relative_path(absolute_moved_link.parent()?, &resolved_target);
+            if new_target == target {
+                return None;
+            }
+            Some(LinkFix {
+                link,
+                moved_link,
+                target,
+                new_target,
+            })
+        })
+        .collect()
+}
+
+/// The directory searched by `--check-links` without an explicit scope:
+/// the enclosing git repository, or the current directory outside of one.
+pub fn default_link_scope() -> Utf8PathBuf {
+    let cwd = absolute_path(Utf8Path::new("."));
+    cwd.ancestors()
+        .find(|directory| directory.join(".git").exists())
+        .unwrap_or(&cwd)
+        .to_owned()
+}
+
+/// Finds the symlinks in `scope`, outside of `paths`, which resolve to
+/// one of `paths` or inside of them. Git metadata directories are not searched.
+pub fn find_incoming_links(scope: &Utf8Path, paths: &[&Utf8Path]) -> Vec<IncomingLink> {
+    let paths: Vec<_> = paths.iter().map(|path| absolute_path(path)).collect();
+    let collecting = CollectingSymlinks {
+        skipped: paths.clone(),
+        ..Default::default()
+    };
+    crate::walk(scope, &collecting);
+    let mut links: Vec<_> = collecting
+        .symlinks
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .filter_map(|(link, target)| {
+            let absolute_link = absolute_path(&link);
+            if paths.iter().any(|path| absolute_link.starts_with(path)) {
+                return None;
+            }
+            let resolved_target = absolute_path(&absolute_link.parent()?.join(&target));
+            if !paths.iter().any(|path| resolved_target.starts_with(path)) {
+                return None;
+            }
+            Some(IncomingLink {
+                link: display_path(&absolute_link),
+                target,
+                resolved_target,
+            })
+        })
+        .collect();
+    links.sort_by(|a, b| a.link.cmp(&b.link));
+    links
+}
+
+// Paths inside the current directory are shown relative to it
+fn display_path(path: &Utf8Path) -> Utf8PathBuf {
+    let cwd = absolute_path(Utf8Path::new("."));
+    match path.strip_prefix(&cwd) {
+        Ok(relative) => relative.to_owned(),
+        Err(_) => path.to_owned(),
+    }
+}
+
+/// Replaces the symlink at `link` with one pointing at `target`. The new link is created
+/// next to it and renamed over it, so that the link is never missing.
+pub fn rewrite_link(link: &Utf8Path, target: &Utf8Path) -> std::io::Result<()> {
+    let dir = Dir::open_parent(link)?;
+    let name = link
+        .file_name()
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+    let temporary = create_temporary_link(&dir, target, name)?;
+    dir.rename(&temporary, name).inspect_err(|_| {
+        let _ = dir.remove_file(&temporary);
+    })
+}
+
+// Returns the name of the new link
+fn create_temporary_link(dir: &Dir, target: &Utf8Path, name: &str) -> std::io::Result<String> {
+    for attempt in 0.. {
+        let temporary = format!(".{}.{}.{}", name, std::process::id(), attempt);
+        match dir.create_symlink(target.as_std_path(), &temporary) {
+            Ok(()) => return Ok(temporary),
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    unreachable!()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_link() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        std::os::unix::fs::symlink("old", root.join("link")).unwrap();
+        rewrite_link(&root.join("link"), Utf8Path::new("new")).unwrap();
+        assert_eq!(
+            std::fs::read_link(root.join("link")).unwrap().to_str(),
+            Some("new")
+        );
+        let names: Vec<_> = root.read_dir_utf8().unwrap().collect();
+        assert_eq!(names.len(), 1);
+    }
+}
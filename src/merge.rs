@@ -0,0 +1,97 @@
+use camino::Utf8Path;
+
+use crate::destination::Located;
+use crate::{Error, Merged, Operation, Plan, Question};
+
+/// Whether `mov` or `cop` writes the sources to the destination.
+#[derive(Clone, Copy)]
+pub(crate) enum Transfer {
+    Move,
+    Copy,
+}
+
+impl Transfer {
+    pub(crate) fn operation(self, from: &Utf8Path, to: &Utf8Path) -> Operation {
+        let (from, to) = (from.to_owned(), to.to_owned());
+        match self {
+            Transfer::Move => Operation::Move { from, to },
+            Transfer::Copy => Operation::Copy { from, to },
+        }
+    }
+}
+
+struct Merge<'a> {
+    plan: &'a mut Plan,
+    transfer: Transfer,
+    confirm: &'a mut dyn FnMut(&Question) -> bool,
+}
+
+/// Plans to move or copy the located sources to their paths, recursing into directories
+/// which exist at both. Entries which don't exist at the destination yet are transferred,
+/// conflicting entries only replace the existing ones when `confirm` agrees to
+/// `Question::Overwrite`. Moved directories are removed unless some of their contents were kept.
+pub(crate) fn plan_merge(
+    plan: &mut Plan,
+    transfer: Transfer,
+    located: &Located,
+    confirm: &mut dyn FnMut(&Question) -> bool,
+) -> Result<(), Error> {
+    let mut merge = Merge {
+        plan,
+        transfer,
+        confirm,
+    };
+    for (source, path) in located.sources.iter().zip(&located.paths) {
+        merge.merge_entry(source, path)?;
+    }
+    Ok(())
+}
+
+impl Merge<'_> {
+    fn merge_entry(&mut self, from: &Utf8Path, to: &Utf8Path) -> Result<(), Error> {
+        let metadata = from
+            .symlink_metadata()
+            .map_err(|error| Error::for_path(from, error))?;
+        if let Ok(existing) = to.symlink_metadata() {
+            if existing.is_dir() && metadata.is_dir() {
+                return self.merge_directory(from, to);
+            }
+            if !(self.confirm)(&Question::Overwrite {
+                paths: &[to.to_owned()],
+                held_open: &[],
+            }) {
+                return Ok(());
+            }
+            self.plan.push(Operation::Delete {
+                path: to.to_owned(),
+                is_dir: existing.is_dir(),
+            });
+        }
+        self.plan.push_merged(
+            self.transfer.operation(from, to),
+            Merged {
+                is_dir: metadata.is_dir(),
+            },
+        );
+        Ok(())
+    }
+
+    fn merge_directory(&mut self, from: &Utf8Path, to: &Utf8Path) -> Result<(), Error> {
+        let names: std::io::Result<Vec<_>> = from.read_dir_utf8().and_then(|entries| {
+            entries
+                .map(|entry| Ok(entry?.file_name().to_owned()))
+                .collect()
+        });
+        let mut names = names.map_err(|error| Error::for_path(from, error))?;
+        names.sort();
+        for name in names {
+            self.merge_entry(&from.join(&name), &to.join(&name))?;
+        }
+        if let Transfer::Move = self.transfer {
+            self.plan.push(Operation::RemoveEmptyDirectory {
+                path: from.to_owned(),
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::destination::{confirm_conflicts, locate, plan_transfers};
+use crate::merge::{plan_merge, Transfer};
+use crate::{
+    absolute_path, execute, find_held_open, find_incoming_links, find_links_to_fix, Backup,
+    Destination, Error, ExecuteOptions, HeldOpen, LinkFix, OnConflict, Operation, Plan, Question,
+    Report, StandardOptions,
+};
+
+/// How `mov` moves, besides the `StandardOptions`.
+#[derive(Default)]
+pub struct MovOptions {
+    pub on_conflict: OnConflict,
+    /// Move the contents of directories into existing directories,
+    /// asking `Question::Overwrite` for each conflicting entry and skipping declined ones
+    pub merge: bool,
+    pub backup: Option<Backup>,
+    /// When moving to another file system, compare the SHA-256 checksums
+    /// of the copies to the originals before removing them
+    pub verify: bool,
+    /// Write the checksums of the moved files to DESTINATION.sha256
+    pub manifest: bool,
+    /// Update the git index like `git mv`
+    pub git: bool,
+    /// Rewrite symlinks without asking `Question::FixLinks` and `Question::RetargetLinks`
+    pub fix_links: bool,
+    /// Search this directory for symlinks pointing into the moved paths to retarget them
+    pub check_links: Option<Utf8PathBuf>,
+    /// List the running processes which hold the moved or replaced paths open
+    /// in the confirmation, or ask `Question::MoveHeldOpen` when nothing else is asked
+    pub check_open: bool,
+}
+
+/// Moves `sources` to `destination`, asking `confirm` before replacing existing entries.
+/// When a step fails, the steps before it are undone as far as possible,
+/// unless `--keep-going` carries on with the remaining ones.
+pub fn mov(
+    options: &StandardOptions,
+    mov_options: &MovOptions,
+    sources: &[&Utf8Path],
+    destination: Destination,
+    confirm: &mut dyn FnMut(&Question) -> bool,
+) -> Result<Report, Error> {
+    let mut plan = Plan::default();
+    let located = locate(&mut plan, sources, destination, options.keep_going)?;
+    if mov_options.merge {
+        plan_merge(&mut plan, Transfer::Move, &located, confirm)?;
+        plan.validate(options.keep_going)?;
+        // The conflicts of a merge are asked about one by one, while planning
+        confirm_conflicts(destination, &[], &find_held(mov_options, &plan), confirm)?;
+    } else {
+        let ask = plan_transfers(
+            &mut plan,
+            Transfer::Move,
+            mov_options.on_conflict,
+            mov_options.backup.as_ref(),
+            &located,
+        );
+        plan.validate(options.keep_going)?;
+        let held_open = find_held(mov_options, &plan);
+        confirm_conflicts(destination, &ask, &held_open, confirm)?;
+    }
+    for link_fix in find_link_fixes(options, mov_options, &plan, confirm) {
+        plan.push(Operation::FixLink(link_fix));
+    }
+    let execute_options = ExecuteOptions {
+        verify: mov_options.verify,
+        manifest: mov_options.manifest,
+        git: mov_options.git,
+        keep_going: options.keep_going,
+        roll_back: !options.keep_going,
+        ..Default::default()
+    };
+    Ok(execute(options, &execute_options, plan))
+}
+
+/// Finds the symlinks which would no longer resolve after the moves of `plan`, and those of them
+/// which `confirm` agrees to fix. With `--force`, they are only fixed with `fix_links`.
+fn find_link_fixes(
+    options: &StandardOptions,
+    mov_options: &MovOptions,
+    plan: &Plan,
+    confirm: &mut dyn FnMut(&Question) -> bool,
+) -> Vec<LinkFix> {
+    if options.force && !mov_options.fix_links {
+        return Vec::new();
+    }
+    let moves: Vec<_> = plan
+        .steps
+        .iter()
+        .filter_map(|step| match &step.operation {
+            Operation::Move { from, to } => Some((from.as_path(), to.as_path())),
+            _ => None,
+        })
+        .collect();
+    // Searching inside the moved directories is slow for large trees, so only done on request
+    let search_directories = mov_options.fix_links || mov_options.check_links.is_some();
+    let mut link_fixes: Vec<_> = moves
+        .iter()
+        .flat_map(|(from, to)| find_links_to_fix(from, to, search_directories))
+        .collect();
+    if !link_fixes.is_empty()
+        && !mov_options.fix_links
+        && !confirm(&Question::FixLinks {
+            link_fixes: &link_fixes,
+        })
+    {
+        link_fixes.clear();
+    }
+    if let Some(scope) = &mov_options.check_links {
+        let mut incoming = find_incoming_link_fixes(scope, &moves);
+        if !incoming.is_empty()
+            && !mov_options.fix_links
+            && !confirm(&Question::RetargetLinks {
+                link_fixes: &incoming,
+            })
+        {
+            incoming.clear();
+        }
+        link_fixes.extend(incoming);
+    }
+    link_fixes
+}
+
+/// The processes which hold the moved or replaced paths of `plan` open, with `check_open`.
+fn find_held(mov_options: &MovOptions, plan: &Plan) -> Vec<HeldOpen> {
+    if !mov_options.check_open {
+        return Vec::new();
+    }
+    let paths: Vec<_> = plan
+        .steps
+        .iter()
+        .filter_map(|step| match &step.operation {
+            Operation::Move { from, .. } => Some(from),
+            Operation::Delete { path, .. } | Operation::Backup { path, .. } => Some(path),
+            _ => None,
+        })
+        .collect();
+    find_held_open(&paths)
+}
+
+fn find_incoming_link_fixes(scope: &Utf8Path, moves: &[(&Utf8Path, &Utf8Path)]) -> Vec<LinkFix> {
+    let sources: Vec<_> = moves.iter().map(|(from, _)| *from).collect();
+    let absolute_destinations: Vec<_> = moves.iter().map(|(_, to)| absolute_path(to)).collect();
+    find_incoming_links(scope, &sources)
+        .into_iter()
+        // Links inside the replaced destinations are deleted
+        .filter(|link| {
+            let absolute_link = absolute_path(&link.link);
+            !absolute_destinations
+                .iter()
+                .any(|destination| absolute_link.starts_with(destination))
+        })
+        .filter_map(|link| moves.iter().find_map(|(from, to)| link.retarget(from, to)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mov_reports_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        std::fs::create_dir_all(root.join("dest")).unwrap();
+        for path in ["a", "b", "dest/b"] {
+            std::fs::write(root.join(path), path).unwrap();
+        }
+        let (a, b, dest) = (root.join("a"), root.join("b"), root.join("dest"));
+        let mut asked = Vec::new();
+        let report = mov(
+            &StandardOptions::quiet(),
+            &MovOptions::default(),
+            &[a.as_path(), b.as_path()],
+            Destination::Into(&dest),
+            &mut |question| {
+                if let Question::Overwrite { paths, .. } = question {
+                    asked.extend(paths.iter().cloned());
+                }
+                true
+            },
+        )
+        .unwrap();
+        assert_eq!(asked, [dest.join("b")]);
+        let operations: Vec<_> = report
+            .done
+            .iter()
+            .map(|done| match &done.step.operation {
+                Operation::Move { to, .. } => format!("M {}", to.strip_prefix(root).unwrap()),
+                Operation::Delete { path, .. } => format!("D {}", path.strip_prefix(root).unwrap()),
+                _ => "other".to_owned(),
+            })
+            .collect();
+        assert_eq!(operations, ["M dest/a", "D dest/b", "M dest/b"]);
+        assert_eq!(std::fs::read_to_string(dest.join("b")).unwrap(), "b");
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use itertools::Itertools;
+
+use crate::{
+    directory_path, execute, resolve_conflict, Backup, Error, ExecuteOptions, Incoming, KeepReason,
+    OnConflict, Operation, Plan, Question, Report, Resolution, StandardOptions,
+};
+
+/// How `new` creates, besides the `StandardOptions`.
+#[derive(Default)]
+pub struct NewOptions {
+    /// The content of the new files, which are empty without it
+    pub content: Option<String>,
+    pub on_conflict: OnConflict,
+    pub backup: Option<Backup>,
+    /// Add the new files to the git index like `git add`
+    pub git: bool,
+}
+
+/// Creates the `directories` and `files` with their ancestors,
+/// asking `confirm` once before overwriting any existing entries.
+pub fn new(
+    options: &StandardOptions,
+    new_options: &NewOptions,
+    directories: &[Utf8PathBuf],
+    files: &[Utf8PathBuf],
+    confirm: &mut dyn FnMut(&Question) -> bool,
+) -> Result<Report, Error> {
+    let directory_paths: BTreeSet<_> = directories.iter().cloned().collect();
+    let file_paths: BTreeSet<_> = files.iter().cloned().collect();
+    let all_directory_paths = ancestor_directories(&directory_paths, &file_paths);
+    check_argument_conflicts(&all_directory_paths, &file_paths)?;
+
+    let conflicts = check_conflicts(
+        new_options.on_conflict,
+        &all_directory_paths,
+        &file_paths,
+        new_options.content.as_deref(),
+    );
+    let mut plan = plan_creation(new_options, &directory_paths, &file_paths, &conflicts);
+    plan.validate(options.keep_going)?;
+    if !conflicts.ask.is_empty()
+        && !confirm(&Question::Overwrite {
+            paths: &conflicts.ask,
+            held_open: &[],
+        })
+    {
+        return Err(Error::Declined);
+    }
+    let execute_options = ExecuteOptions {
+        git: new_options.git,
+        keep_going: options.keep_going,
+        ..Default::default()
+    };
+    Ok(execute(options, &execute_options, plan))
+}
+
+fn plan_creation(
+    new_options: &NewOptions,
+    directory_paths: &BTreeSet<Utf8PathBuf>,
+    file_paths: &BTreeSet<Utf8PathBuf>,
+    conflicts: &Conflicts,
+) -> Plan {
+    let mut plan = Plan::default();
+    let mut reserved = directory_paths.iter().chain(file_paths).cloned().collect();
+    let in_the_way = conflicts
+        .clashing_with_directories
+        .iter()
+        .filter(|(_, metadata)| !metadata.is_dir())
+        .chain(
+            conflicts
+                .clashing_with_files
+                .iter()
+                .filter(|(_, metadata)| metadata.is_dir()),
+        );
+    for (path, metadata) in in_the_way {
+        let resolution = conflicts.resolution(path);
+        plan.push_resolved(
+            replace(&new_options.backup, path, metadata.is_dir(), &mut reserved),
+            resolution,
+        );
+    }
+
+    for path in directory_paths {
+        if let Some(resolution) = conflicts.find_skipping_resolution(path) {
+            plan.push_resolved(skip(directory_path(path)), resolution.clone());
+            continue;
+        }
+        let clashing = conflicts.clashing_with_directories.get(path);
+        if clashing.is_some_and(|metadata| metadata.is_dir()) {
+            plan.push(Operation::Keep {
+                path: path.clone(),
+                reason: KeepReason::Existing { is_dir: true },
+            });
+            continue;
+        }
+        plan.push_resolved(
+            Operation::CreateDirectory { path: path.clone() },
+            conflicts.resolution(path),
+        );
+    }
+
+    for path in file_paths {
+        if let Some(resolution) = conflicts.find_skipping_resolution(path) {
+            plan.push_resolved(skip(path.clone()), resolution.clone());
+            continue;
+        }
+        let resolution = conflicts.resolution(path);
+        let path = match &resolution {
+            Resolution::Rename(renamed) => renamed.clone(),
+            _ => path.clone(),
+        };
+        let clashing = conflicts.clashing_with_files.get(&path);
+        let content = new_options.content.clone();
+        if content.is_none()
+            && clashing.is_some_and(|metadata| !metadata.is_dir() && metadata.len() == 0)
+        {
+            plan.push(Operation::Keep {
+                path,
+                reason: KeepReason::Existing { is_dir: false },
+            });
+            continue;
+        }
+        let mut overwrite = clashing.is_some_and(|metadata| !metadata.is_dir());
+        if overwrite && new_options.backup.is_some() {
+            plan.push_resolved(
+                replace(&new_options.backup, &path, false, &mut reserved),
+                resolution.clone(),
+            );
+            overwrite = false;
+        }
+        plan.push_resolved(
+            Operation::CreateFile {
+                path,
+                content,
+                overwrite,
+            },
+            resolution,
+        );
+    }
+    plan
+}
+
+/// Deletes the entry in the way of a new path, or moves it to a backup path
+/// which is not in `reserved`.
+fn replace(
+    backup: &Option<Backup>,
+    path: &Utf8Path,
+    is_dir: bool,
+    reserved: &mut Vec<Utf8PathBuf>,
+) -> Operation {
+    let path = path.to_owned();
+    match backup {
+        Some(backup) => Operation::Backup {
+            backup: backup.path_for(&path, reserved),
+            path,
+            is_dir,
+        },
+        None => Operation::Delete { path, is_dir },
+    }
+}
+
+fn skip(path: Utf8PathBuf) -> Operation {
+    Operation::Keep {
+        path,
+        reason: KeepReason::Skipped { source: None },
+    }
+}
+
+/// The directories to create for the `directory_paths` and the parents of the `file_paths`.
+fn ancestor_directories(
+    directory_paths: &BTreeSet<Utf8PathBuf>,
+    file_paths: &BTreeSet<Utf8PathBuf>,
+) -> BTreeSet<Utf8PathBuf> {
+    directory_paths
+        .iter()
+        .flat_map(|path| path.ancestors())
+        .map(|path| path.to_owned())
+        .chain(file_paths.iter().flat_map(|path| {
+            path.parent()
+                .map(|parent| parent.ancestors())
+                .into_iter()
+                .flatten()
+                .map(|path| path.to_owned())
+        }))
+        .filter(|path| path != "")
+        .collect()
+}
+
+fn check_argument_conflicts(
+    all_directory_paths: &BTreeSet<Utf8PathBuf>,
+    file_paths: &BTreeSet<Utf8PathBuf>,
+) -> Result<(), Error> {
+    let clashing: BTreeSet<_> = all_directory_paths.intersection(file_paths).collect();
+
+    if !clashing.is_empty() {
+        return Err(Error::Invalid(format!(
+            "Cannot create both file and a directory at:\n{}",
+            clashing.into_iter().join("\n")
+        )));
+    }
+    Ok(())
+}
+
+type Clashing = BTreeMap<Utf8PathBuf, std::fs::Metadata>;
+
+/// The existing entries at the paths, without those which are kept.
+struct Conflicts {
+    clashing_with_directories: Clashing,
+    clashing_with_files: Clashing,
+    resolutions: BTreeMap<Utf8PathBuf, Resolution>,
+    /// The paths the user has to decide about
+    ask: Vec<Utf8PathBuf>,
+}
+
+/// Finds the existing entries at the paths and decides with `on_conflict` which of them
+/// are replaced. Entries which are kept are left out of the clashing entries.
+fn check_conflicts(
+    on_conflict: OnConflict,
+    all_directory_paths: &BTreeSet<Utf8PathBuf>,
+    file_paths: &BTreeSet<Utf8PathBuf>,
+    content: Option<&str>,
+) -> Conflicts {
+    let mut clashing_with_directories = existing_entries(all_directory_paths);
+    let mut clashing_with_files = existing_entries(file_paths);
+
+    let conflicting: Vec<_> = clashing_with_directories
+        .iter()
+        .filter(|(_, metadata)| !metadata.is_dir())
+        .map(|(path, _)| path.clone())
+        .chain(
+            clashing_with_files
+                .iter()
+                .filter(|(_, metadata)| metadata.is_dir() || metadata.len() > 0)
+                .map(|(path, _)| path.clone()),
+        )
+        .collect();
+
+    let mut resolutions = BTreeMap::new();
+    let mut reserved = all_directory_paths
+        .iter()
+        .chain(file_paths)
+        .cloned()
+        .collect();
+    let mut ask = Vec::new();
+    for path in conflicting {
+        let is_file = file_paths.contains(&path);
+        let incoming = Incoming::Content(if is_file {
+            content.map_or(0, |content| content.len() as u64)
+        } else {
+            0
+        });
+        match resolve_conflict(on_conflict, &incoming, &path, &mut reserved) {
+            // Renaming a directory would move the paths inside it
+            Some(Resolution::Rename(_)) if !is_file => {
+                resolutions.insert(path, Resolution::Skip(OnConflict::Rename));
+            }
+            Some(resolution) => {
+                resolutions.insert(path, resolution);
+            }
+            None => ask.push(path),
+        }
+    }
+
+    for (path, resolution) in &resolutions {
+        if matches!(resolution, Resolution::Skip(_) | Resolution::Rename(_)) {
+            clashing_with_directories.remove(path);
+            clashing_with_files.remove(path);
+        }
+    }
+
+    Conflicts {
+        clashing_with_directories,
+        clashing_with_files,
+        resolutions,
+        ask,
+    }
+}
+
+impl Conflicts {
+    /// The resolution which skipped `path` or one of its ancestors.
+    fn find_skipping_resolution(&self, path: &Utf8Path) -> Option<&Resolution> {
+        path.ancestors()
+            .filter_map(|ancestor| self.resolutions.get(ancestor))
+            .find(|resolution| matches!(resolution, Resolution::Skip(_)))
+    }
+
+    fn resolution(&self, path: &Utf8Path) -> Resolution {
+        self.resolutions
+            .get(path)
+            .cloned()
+            .unwrap_or(Resolution::NoConflict)
+    }
+}
+
+fn existing_entries(paths: &BTreeSet<Utf8PathBuf>) -> Clashing {
+    paths
+        .iter()
+        .filter_map(|path| {
+            path.symlink_metadata()
+                .ok()
+                .map(|metadata| (path.clone(), metadata))
+        })
+        .collect()
+}
@@ -0,0 +1,141 @@
+#[cfg(target_os = "linux")]
+use std::collections::BTreeSet;
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+#[cfg(target_os = "linux")]
+use crate::absolute_path;
+
+#[cfg(target_os = "linux")]
+const PROC: &str = "/proc";
+
+/// A running process which holds a path open.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HeldOpen {
+    pub pid: u32,
+    /// The name of the process' command
+    pub name: String,
+    /// The held path, at or inside one of the checked paths
+    pub path: Utf8PathBuf,
+    pub how: Holding,
+}
+
+/// How a process holds a path open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Holding {
+    File,
+    WorkingDirectory,
+    Mapped,
+}
+
+impl Holding {
+    pub fn description(self) -> &'static str {
+        match self {
+            Holding::File => "open",
+            Holding::WorkingDirectory => "working directory",
+            Holding::Mapped => "mapped",
+        }
+    }
+}
+
+/// Finds the processes which have files open at or inside `paths`, use them as their
+/// working directory or map them into memory, sorted by PID. Only processes whose
+/// entries in `/proc` can be read are found, none where there is no `/proc`.
+/// The working directory of the parent process is left out, the shell follows it
+/// with `--track-cwd-change`.
+#[cfg(target_os = "linux")]
+pub fn find_held_open<P: AsRef<Utf8Path>>(paths: &[P]) -> Vec<HeldOpen> {
+    let targets: Vec<_> = paths
+        .iter()
+        .filter_map(|path| canonical_entry(path.as_ref()))
+        .collect();
+    if targets.is_empty() {
+        return Vec::new();
+    }
+    let Ok(processes) = std::fs::read_dir(PROC) else {
+        return Vec::new();
+    };
+    let own_pid = std::process::id();
+    let parent_pid = std::os::unix::process::parent_id();
+    let mut held_open = BTreeSet::new();
+    for process in processes.flatten() {
+        let Some(pid) = process
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse().ok())
+        else {
+            continue;
+        };
+        if pid == own_pid {
+            continue;
+        }
+        let process_path = process.path();
+        let held = held_paths(&process_path)
+            .into_iter()
+            .filter(|(_, how)| pid != parent_pid || *how != Holding::WorkingDirectory)
+            .filter(|(path, _)| targets.iter().any(|target| path.starts_with(target)))
+            .collect::<Vec<_>>();
+        if held.is_empty() {
+            continue;
+        }
+        let name = std::fs::read_to_string(process_path.join("comm"))
+            .map(|comm| comm.trim_end().to_owned())
+            .unwrap_or_default();
+        for (path, how) in held {
+            held_open.insert(HeldOpen {
+                pid,
+                name: name.clone(),
+                path,
+                how,
+            });
+        }
+    }
+    held_open.into_iter().collect()
+}
+
+/// Finding the processes which hold paths open needs `/proc`, so none are found elsewhere.
+#[cfg(not(target_os = "linux"))]
+pub fn find_held_open<P: AsRef<Utf8Path>>(_: &[P]) -> Vec<HeldOpen> {
+    Vec::new()
+}
+
+#[cfg(target_os = "linux")]
+/// The path of the entry itself with its parent resolved, so that a symlink is not followed.
+fn canonical_entry(path: &Utf8Path) -> Option<Utf8PathBuf> {
+    path.symlink_metadata().ok()?;
+    let absolute = absolute_path(path);
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => Some(parent.canonicalize_utf8().ok()?.join(name)),
+        _ => Some(absolute),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn held_paths(process_path: &std::path::Path) -> Vec<(Utf8PathBuf, Holding)> {
+    let mut held = Vec::new();
+    if let Ok(descriptors) = std::fs::read_dir(process_path.join("fd")) {
+        for descriptor in descriptors.flatten() {
+            if let Some(path) = read_link(&descriptor.path()) {
+                held.push((path, Holding::File));
+            }
+        }
+    }
+    if let Some(path) = read_link(&process_path.join("cwd")) {
+        held.push((path, Holding::WorkingDirectory));
+    }
+    if let Ok(maps) = std::fs::read_to_string(process_path.join("maps")) {
+        // The path is the last column, and the columns before it never contain a slash
+        for line in maps.lines() {
+            if let Some(start) = line.find('/') {
+                held.push((Utf8PathBuf::from(&line[start..]), Holding::Mapped));
+            }
+        }
+    }
+    held
+}
+
+#[cfg(target_os = "linux")]
+fn read_link(path: &std::path::Path) -> Option<Utf8PathBuf> {
+    let target = std::fs::read_link(path).ok()?;
+    Utf8PathBuf::from_path_buf(target).ok()
+}
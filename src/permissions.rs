@@ -0,0 +1,198 @@
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::WalkError;
+
+const STICKY: u32 = 0o1000;
+
+/// Checks that entries can be added to and removed from `directory`.
+pub(crate) fn check_writable_directory(directory: &Utf8Path) -> Result<(), WalkError> {
+    check_flags(directory, false)?;
+    check_access(directory)
+}
+
+/// Checks that the existing file at `path` can be written to in place.
+pub(crate) fn check_writable_file(path: &Utf8Path) -> Result<(), WalkError> {
+    check_flags(path, true)?;
+    check_access(path)
+}
+
+/// Checks that the entry at `path` can be removed from its directory, or renamed within
+/// the file system. A directory which leaves its parent is itself written to.
+pub(crate) fn check_removable(path: &Utf8Path, leaves_parent: bool) -> Result<(), WalkError> {
+    let Ok(metadata) = path.symlink_metadata() else {
+        return Ok(());
+    };
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_str().is_empty() => parent,
+        _ => Utf8Path::new("."),
+    };
+    check_flags(parent, true)?;
+    check_access(parent)?;
+    check_flags_of(path, &metadata, true)?;
+    if let Ok(parent_metadata) = parent.metadata() {
+        check_sticky(path, &parent_metadata, &metadata)?;
+    }
+    if leaves_parent && metadata.is_dir() {
+        check_access(path)?;
+    }
+    Ok(())
+}
+
+/// Checks that the directory at `path` can be removed and that its entries can be
+/// removed from it. Deeper entries are left to the deletion to report, so that checking
+/// doesn't walk the whole tree first.
+pub(crate) fn check_removable_directory(path: &Utf8Path) -> Result<(), WalkError> {
+    check_removable(path, false)?;
+    if !path
+        .symlink_metadata()
+        .is_ok_and(|metadata| metadata.is_dir())
+    {
+        return Ok(());
+    }
+    check_flags(path, true)?;
+    check_access(path)
+}
+
+fn check_flags(path: &Utf8Path, removing: bool) -> Result<(), WalkError> {
+    match path.symlink_metadata() {
+        Ok(metadata) => check_flags_of(path, &metadata, removing),
+        Err(_) => Ok(()),
+    }
+}
+
+// Immutable entries cannot be changed at all, append-only ones only cannot lose contents
+fn check_flags_of(
+    path: &Utf8Path,
+    metadata: &std::fs::Metadata,
+    removing: bool,
+) -> Result<(), WalkError> {
+    let (immutable, append_only) = inode_flags(path, metadata);
+    if immutable {
+        return Err(denied(path, "Immutable (chattr +i)"));
+    }
+    if append_only && removing {
+        return Err(denied(path, "Append-only (chattr +a)"));
+    }
+    Ok(())
+}
+
+fn denied(path: &Utf8Path, message: &str) -> WalkError {
+    WalkError {
+        path: path.to_owned(),
+        error: std::io::Error::new(std::io::ErrorKind::PermissionDenied, message),
+    }
+}
+
+#[cfg(unix)]
+fn check_access(path: &Utf8Path) -> Result<(), WalkError> {
+    use rustix::fs::{Access, AtFlags, CWD};
+    let is_dir = path.metadata().is_ok_and(|metadata| metadata.is_dir());
+    let access = if is_dir {
+        Access::WRITE_OK | Access::EXEC_OK
+    } else {
+        Access::WRITE_OK
+    };
+    match rustix::fs::accessat(CWD, path.as_std_path(), access, AtFlags::EACCESS) {
+        Ok(()) => Ok(()),
+        Err(rustix::io::Errno::ACCESS) if is_dir => Err(denied(path, "Directory is not writable")),
+        Err(rustix::io::Errno::ACCESS) => Err(denied(path, "File is not writable")),
+        Err(errno) => Err(WalkError {
+            path: path.to_owned(),
+            error: errno.into(),
+        }),
+    }
+}
+
+#[cfg(not(unix))]
+fn check_access(_path: &Utf8Path) -> Result<(), WalkError> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn check_sticky(
+    path: &Utf8Path,
+    parent: &std::fs::Metadata,
+    metadata: &std::fs::Metadata,
+) -> Result<(), WalkError> {
+    use std::os::unix::fs::MetadataExt;
+    let user = rustix::process::geteuid().as_raw();
+    if sticky_allows(parent.mode(), parent.uid(), metadata.uid(), user) {
+        Ok(())
+    } else {
+        Err(denied(
+            path,
+            "Owned by another user in a directory with the sticky bit",
+        ))
+    }
+}
+
+#[cfg(not(unix))]
+fn check_sticky(
+    _path: &Utf8Path,
+    _parent: &std::fs::Metadata,
+    _metadata: &std::fs::Metadata,
+) -> Result<(), WalkError> {
+    Ok(())
+}
+
+/// Whether `user` may remove or rename an entry owned by `owner` from a directory
+/// with `mode` owned by `directory_owner`. With the sticky bit, only the owners and root may.
+fn sticky_allows(mode: u32, directory_owner: u32, owner: u32, user: u32) -> bool {
+    mode & STICKY == 0 || user == 0 || user == owner || user == directory_owner
+}
+
+/// Whether the entry is immutable and whether it is append-only, only regular files
+/// and directories are opened to read their flags.
+#[cfg(target_os = "linux")]
+fn inode_flags(path: &Utf8Path, metadata: &std::fs::Metadata) -> (bool, bool) {
+    use rustix::fs::{IFlags, Mode, OFlags};
+    if !metadata.is_dir() && !metadata.is_file() {
+        return (false, false);
+    }
+    let flags = rustix::fs::open(
+        path.as_std_path(),
+        OFlags::RDONLY | OFlags::NONBLOCK | OFlags::NOFOLLOW | OFlags::NOCTTY | OFlags::CLOEXEC,
+        Mode::empty(),
+    )
+    .and_then(rustix::fs::ioctl_getflags);
+    match flags {
+        Ok(flags) => (
+            flags.contains(IFlags::IMMUTABLE),
+            flags.contains(IFlags::APPEND),
+        ),
+        Err(_) => (false, false),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn inode_flags(_path: &Utf8Path, _metadata: &std::fs::Metadata) -> (bool, bool) {
+    (false, false)
+}
+
+/// The closest existing directory at or above `path`, where new entries are added.
+pub(crate) fn existing_directory(path: &Utf8Path) -> Option<Utf8PathBuf> {
+    path.ancestors()
+        .map(|ancestor| {
+            if ancestor.as_str().is_empty() {
+                Utf8Path::new(".")
+            } else {
+                ancestor
+            }
+        })
+        .find(|ancestor| ancestor.is_dir())
+        .map(Utf8Path::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sticky_allows() {
+        assert!(sticky_allows(0o777, 1, 2, 3));
+        assert!(!sticky_allows(0o1777, 1, 2, 3));
+        assert!(sticky_allows(0o1777, 1, 2, 2));
+        assert!(sticky_allows(0o1777, 1, 2, 1));
+        assert!(sticky_allows(0o1777, 1, 2, 0));
+    }
+}
@@ -0,0 +1,1212 @@
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use colored::Colorize;
+
+use crate::backup::move_to_backup;
+use crate::permissions::{
+    check_removable, check_removable_directory, check_writable_directory, check_writable_file,
+    existing_directory,
+};
+use crate::{
+    absolute_path, color_new, copy_tree, directory_path, git_enabled, handle_cwd, hash_tree,
+    message_success, move_tree, move_tree_verified, path_string, remove_tree, rewrite_link,
+    stage_new, tracked_entries, verify_tree, write_manifest, CopyOptions, CopySummary, Error,
+    LinkFix, Resolution, StandardOptions, Tracked, WalkError, FAILURE, PARTIAL_FAILURE, SUCCESS,
+};
+
+const COLOR: colored::Color = colored::Color::BrightGreen;
+
+/// A change to the file system, or an entry which is left alone.
+pub enum Operation {
+    /// Creates the directory with its missing ancestors
+    CreateDirectory {
+        path: Utf8PathBuf,
+    },
+    /// Creates the file with `content`, or empty, overwriting the existing file with `overwrite`
+    CreateFile {
+        path: Utf8PathBuf,
+        content: Option<String>,
+        overwrite: bool,
+    },
+    Move {
+        from: Utf8PathBuf,
+        to: Utf8PathBuf,
+    },
+    Copy {
+        from: Utf8PathBuf,
+        to: Utf8PathBuf,
+    },
+    Delete {
+        path: Utf8PathBuf,
+        is_dir: bool,
+    },
+    /// Moves the existing entry at `path` out of the way to `backup`
+    Backup {
+        path: Utf8PathBuf,
+        backup: Utf8PathBuf,
+        is_dir: bool,
+    },
+    FixLink(LinkFix),
+    /// Removes the directory after its entries were moved, unless some of them were kept
+    RemoveEmptyDirectory {
+        path: Utf8PathBuf,
+    },
+    /// Nothing is done at `path`, which is shown as given
+    Keep {
+        path: Utf8PathBuf,
+        reason: KeepReason,
+    },
+}
+
+/// Why an `Operation::Keep` leaves its path alone.
+pub enum KeepReason {
+    /// `source` is already located at the path
+    AlreadyLocated { source: Utf8PathBuf },
+    /// The directory, or the empty file, already exists
+    Existing { is_dir: bool },
+    /// `--on-conflict` kept the existing entry, instead of writing `source` or a new entry
+    Skipped { source: Option<Utf8PathBuf> },
+}
+
+pub struct Step {
+    pub operation: Operation,
+    /// How a conflict at the path of the operation was resolved
+    pub resolution: Resolution,
+    /// Set for the entries written by `--merge`, which are shown as new or replaced entries
+    pub merged: Option<Merged>,
+}
+
+/// An entry moved or copied into an existing directory by `--merge`.
+pub struct Merged {
+    pub is_dir: bool,
+}
+
+/// The steps of a command, in the order they are carried out by `execute`.
+#[derive(Default)]
+pub struct Plan {
+    pub steps: Vec<Step>,
+    /// Errors of the items which were left out of the steps with `--keep-going`
+    pub failed: Vec<WalkError>,
+}
+
+impl Operation {
+    /// The path the operation writes to, with the path whose content ends up there.
+    fn written(&self) -> Option<(&Utf8Path, &Utf8Path)> {
+        match self {
+            Operation::CreateDirectory { path } | Operation::CreateFile { path, .. } => {
+                Some((path, path))
+            }
+            Operation::Move { from, to } | Operation::Copy { from, to } => Some((from, to)),
+            Operation::Backup { path, backup, .. } => Some((path, backup)),
+            _ => None,
+        }
+    }
+
+    /// The existing path the operation reads, moves or deletes.
+    fn used(&self) -> Option<&Utf8Path> {
+        match self {
+            Operation::Move { from, .. } | Operation::Copy { from, .. } => Some(from),
+            Operation::Delete { path, .. } | Operation::Backup { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// The existing path which is gone after the operation, with how it went.
+    fn removed(&self) -> Option<(&Utf8Path, &'static str)> {
+        match self {
+            Operation::Move { from, .. } => Some((from, "moved")),
+            Operation::Delete { path, .. } => Some((path, "deleted")),
+            Operation::Backup { path, .. } => Some((path, "backed up")),
+            _ => None,
+        }
+    }
+
+    /// Whether the operation deletes or backs up the entry which `next` writes in its place.
+    fn makes_way_for(&self, next: &Operation) -> bool {
+        let written = next.written().map(|(_, to)| to);
+        match self {
+            Operation::Delete { path, .. } | Operation::Backup { path, .. } => {
+                written == Some(path.as_path())
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Plan {
+    pub fn push(&mut self, operation: Operation) {
+        self.push_resolved(operation, Resolution::NoConflict);
+    }
+
+    pub fn push_resolved(&mut self, operation: Operation, resolution: Resolution) {
+        self.steps.push(Step {
+            operation,
+            resolution,
+            merged: None,
+        });
+    }
+
+    pub fn push_merged(&mut self, operation: Operation, merged: Merged) {
+        self.steps.push(Step {
+            operation,
+            resolution: Resolution::NoConflict,
+            merged: Some(merged),
+        });
+    }
+
+    /// Errors if two steps write to the same path, if a directory would be moved or copied
+    /// into itself, if a step uses a path inside one which an earlier step removes,
+    /// or if the permissions of the involved directories would make a step fail.
+    /// With `keep_going`, the steps which would fail for their permissions are left out
+    /// instead, and their errors added to `failed`.
+    pub fn validate(&mut self, keep_going: bool) -> Result<(), Error> {
+        self.check_collisions()?;
+        self.check_cycles()?;
+        self.check_ancestors()?;
+        let step_errors = self.check_permissions();
+        if keep_going {
+            self.leave_out_failing(step_errors);
+            return Ok(());
+        }
+        let mut errors = Vec::new();
+        for error in step_errors.into_iter().flatten() {
+            add_unique(&mut errors, error);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Paths(errors))
+        }
+    }
+
+    /// Leaves out the steps with errors, along with the deletions and backups
+    /// which make way for them or for which they make way.
+    fn leave_out_failing(&mut self, step_errors: Vec<Option<WalkError>>) {
+        let mut failing: Vec<_> = step_errors.iter().map(Option::is_some).collect();
+        for i in 1..self.steps.len() {
+            let paired = self.steps[i - 1]
+                .operation
+                .makes_way_for(&self.steps[i].operation);
+            if paired && (failing[i - 1] || failing[i]) {
+                failing[i - 1] = true;
+                failing[i] = true;
+            }
+        }
+        for error in step_errors.into_iter().flatten() {
+            add_unique(&mut self.failed, error);
+        }
+        let steps = std::mem::take(&mut self.steps);
+        self.steps = steps
+            .into_iter()
+            .zip(failing)
+            .filter_map(|(step, failing)| (!failing).then_some(step))
+            .collect();
+    }
+
+    fn check_collisions(&self) -> Result<(), Error> {
+        let mut written = BTreeMap::new();
+        for step in &self.steps {
+            let Some((source, target)) = step.operation.written() else {
+                continue;
+            };
+            if let Some(earlier) = written.insert(absolute_path(target), source) {
+                return Err(Error::Invalid(format!(
+                    "Cannot write both \"{}\" and \"{}\" to \"{}\"",
+                    earlier, source, target
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_cycles(&self) -> Result<(), Error> {
+        for step in &self.steps {
+            let (verb, from, to) = match &step.operation {
+                Operation::Move { from, to } => ("move", from, to),
+                Operation::Copy { from, to } => ("copy", from, to),
+                _ => continue,
+            };
+            if is_inside_itself(from, to) {
+                return Err(Error::Invalid(format!(
+                    "Cannot {} \"{}\" into itself at \"{}\"",
+                    verb, from, to
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_ancestors(&self) -> Result<(), Error> {
+        let removed: Vec<_> = self
+            .steps
+            .iter()
+            .filter_map(|step| step.operation.removed())
+            .map(|(path, how)| (path, absolute_path(path), how))
+            .collect();
+        for path in self.steps.iter().filter_map(|step| step.operation.used()) {
+            let absolute = absolute_path(path);
+            for (ancestor, absolute_ancestor, how) in &removed {
+                if absolute != *absolute_ancestor && absolute.starts_with(absolute_ancestor) {
+                    return Err(Error::Invalid(format!(
+                        "\"{}\" is inside \"{}\", which is {}",
+                        path, ancestor, how
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The error of each step which its permissions would make fail.
+    fn check_permissions(&self) -> Vec<Option<WalkError>> {
+        let writable_parent = |path: &Utf8Path| match path.parent().and_then(existing_directory) {
+            Some(directory) => check_writable_directory(&directory),
+            None => Ok(()),
+        };
+        self.steps
+            .iter()
+            .map(|step| match &step.operation {
+                Operation::Delete { path, is_dir: true } => check_removable_directory(path),
+                Operation::Delete { path, .. } => check_removable(path, false),
+                Operation::Move { from, to } => {
+                    let leaves_parent = absolute_path(from).parent() != absolute_path(to).parent();
+                    check_removable(from, leaves_parent).and_then(|()| writable_parent(to))
+                }
+                Operation::Backup { path, backup, .. } => {
+                    check_removable(path, true).and_then(|()| writable_parent(backup))
+                }
+                Operation::CreateFile {
+                    path,
+                    overwrite: true,
+                    ..
+                } if path.is_file() => check_writable_file(path),
+                Operation::CreateDirectory { path }
+                | Operation::CreateFile { path, .. }
+                | Operation::Copy { to: path, .. } => writable_parent(path),
+                Operation::FixLink(_)
+                | Operation::RemoveEmptyDirectory { .. }
+                | Operation::Keep { .. } => Ok(()),
+            })
+            .map(Result::err)
+            .collect()
+    }
+}
+
+// Several steps can fail for the same path, which is reported once
+fn add_unique(errors: &mut Vec<WalkError>, error: WalkError) {
+    if !errors.iter().any(|earlier| earlier.path == error.path) {
+        errors.push(error);
+    }
+}
+
+// Whether `to` is inside the directory `from`, also through symlinks
+fn is_inside_itself(from: &Utf8Path, to: &Utf8Path) -> bool {
+    if !from
+        .symlink_metadata()
+        .is_ok_and(|metadata| metadata.is_dir())
+    {
+        return false;
+    }
+    let existing = if to.exists() {
+        Some(to)
+    } else {
+        crate::find_existing_ancestor_directory(to)
+    };
+    let (Ok(from), Some(Ok(existing))) = (
+        from.canonicalize_utf8(),
+        existing.map(Utf8Path::canonicalize_utf8),
+    ) else {
+        return false;
+    };
+    existing.starts_with(from)
+}
+
+/// How `execute` carries out the steps, besides the `StandardOptions`.
+#[derive(Default)]
+pub struct ExecuteOptions {
+    pub copy: CopyOptions,
+    /// Compare the SHA-256 checksums of copies to the originals
+    pub verify: bool,
+    /// With `verify`, write the checksums of moved or copied files to DESTINATION.sha256
+    pub manifest: bool,
+    /// Update the git index for created, moved and deleted entries,
+    /// also done in repositories which set `human-utils.git` to true
+    pub git: bool,
+    /// Carry on with the remaining steps after one fails
+    pub keep_going: bool,
+    /// Undo the steps carried out before a failing one, in reverse order. Deleted entries
+    /// are renamed out of the way instead, and only deleted once all steps succeeded
+    pub roll_back: bool,
+}
+
+/// A step which was carried out, or only listed with `--dry-run`.
+pub struct Done {
+    pub step: Step,
+    /// The closest directory above the written path which existed before the plan was executed
+    pub existing_ancestor: Option<Utf8PathBuf>,
+    /// Whether the git index was updated
+    pub staged: bool,
+    /// Whether copies were compared to the originals
+    pub verified: bool,
+    pub manifest: Option<Utf8PathBuf>,
+    pub summary: CopySummary,
+    /// The missing directories which were created for the written path, deepest first
+    pub created_directories: Vec<Utf8PathBuf>,
+    /// Where a deletion renamed its entry with `roll_back`
+    pub set_aside: Option<SetAside>,
+}
+
+/// An entry which a deletion renamed to a hidden sibling, so that it can be renamed back.
+pub struct SetAside {
+    pub path: Utf8PathBuf,
+    /// The index entries whose removal was staged
+    tracked: Option<Tracked>,
+}
+
+/// What `execute` did, in the order of the plan.
+#[derive(Default)]
+pub struct Report {
+    pub done: Vec<Done>,
+    /// Errors of the steps which failed, the steps after the first one are skipped
+    /// without `keep_going`
+    pub failed: Vec<WalkError>,
+    /// Errors which did not keep a step from being carried out
+    pub warnings: Vec<WalkError>,
+    /// The steps which were undone after a failure with `roll_back`, in the order they were undone
+    pub restored: Vec<Done>,
+    /// Errors of the steps which could not be undone, they stay in `done`
+    pub not_restored: Vec<WalkError>,
+}
+
+impl Report {
+    /// `SUCCESS` when nothing failed, `PARTIAL_FAILURE` with `--keep-going`
+    /// when some steps were carried out anyway, `FAILURE` otherwise.
+    pub fn exit_code(&self, options: &StandardOptions) -> i32 {
+        if self.failed.is_empty() {
+            SUCCESS
+        } else if options.keep_going && self.carried_out() > 0 {
+            PARTIAL_FAILURE
+        } else {
+            FAILURE
+        }
+    }
+
+    /// The user-visible items which were carried out. An entry deleted or backed up
+    /// to make way for another is counted with it, and fixed symlinks with the moves.
+    fn carried_out(&self) -> usize {
+        self.done
+            .iter()
+            .enumerate()
+            .filter(|(index, done)| {
+                let makes_way = self
+                    .done
+                    .get(index + 1)
+                    .is_some_and(|next| done.step.operation.makes_way_for(&next.step.operation));
+                !makes_way
+                    && !matches!(
+                        done.step.operation,
+                        Operation::Keep { .. } | Operation::FixLink(_)
+                    )
+            })
+            .count()
+    }
+}
+
+/// Carries out the steps of `plan`, or only reports them with `--dry-run`.
+pub fn execute(options: &StandardOptions, execute_options: &ExecuteOptions, plan: Plan) -> Report {
+    let existing_ancestors: Vec<_> = plan
+        .steps
+        .iter()
+        .map(|step| {
+            let (_, target) = step.operation.written()?;
+            find_existing_ancestor(target)
+        })
+        .collect();
+    let mut report = Report {
+        failed: plan.failed,
+        ..Default::default()
+    };
+    let mut steps = plan.steps.into_iter().zip(existing_ancestors).peekable();
+    while let Some((step, existing_ancestor)) = steps.next() {
+        let mut done = Done {
+            step,
+            existing_ancestor,
+            staged: false,
+            verified: false,
+            manifest: None,
+            summary: CopySummary::default(),
+            created_directories: Vec::new(),
+            set_aside: None,
+        };
+        if !options.dry_run {
+            let carried_out = check_precondition(&done.step.operation).and_then(|()| {
+                carry_out(options, execute_options, &mut done, &mut report.warnings)
+            });
+            if let Err(errors) = carried_out {
+                report.failed.extend(errors);
+                if execute_options.keep_going {
+                    // The entry in the way is still there, so its replacement fails as well
+                    if steps
+                        .peek()
+                        .is_some_and(|(next, _)| done.step.operation.makes_way_for(&next.operation))
+                    {
+                        steps.next();
+                    }
+                    continue;
+                }
+                if execute_options.roll_back {
+                    // Directories created before the step failed are still empty
+                    for directory in &done.created_directories {
+                        let _ = std::fs::remove_dir(directory);
+                    }
+                    roll_back(options, &mut report);
+                }
+                break;
+            }
+        }
+        report.done.push(done);
+    }
+    if report.failed.is_empty() {
+        delete_set_aside(options, &mut report);
+    }
+    report
+}
+
+/// Deletes the entries which deletions renamed out of the way, once nothing is rolled back.
+fn delete_set_aside(options: &StandardOptions, report: &mut Report) {
+    for done in &report.done {
+        let (Some(set_aside), Operation::Delete { is_dir, .. }) =
+            (&done.set_aside, &done.step.operation)
+        else {
+            continue;
+        };
+        let path = &set_aside.path;
+        let deleted = if *is_dir {
+            remove_tree(options, path)
+        } else {
+            std::fs::remove_file(path).map_err(|error| fail(path, error))
+        };
+        if let Err(errors) = deleted {
+            report.warnings.extend(errors);
+        }
+    }
+}
+
+/// Undoes the steps in `report.done` from the last one on, moving them to `report.restored`.
+fn roll_back(options: &StandardOptions, report: &mut Report) {
+    let mut kept = Vec::new();
+    while let Some(done) = report.done.pop() {
+        match undo(options, &done) {
+            Ok(()) => report.restored.push(done),
+            Err(errors) => {
+                report.not_restored.extend(errors);
+                kept.push(done);
+            }
+        }
+    }
+    kept.reverse();
+    report.done = kept;
+}
+
+// Also removes the directories which the step created
+fn undo(options: &StandardOptions, done: &Done) -> Result<(), Vec<WalkError>> {
+    undo_operation(options, done)?;
+    for directory in &done.created_directories {
+        std::fs::remove_dir(directory).map_err(|error| fail(directory, error))?;
+    }
+    Ok(())
+}
+
+fn undo_operation(options: &StandardOptions, done: &Done) -> Result<(), Vec<WalkError>> {
+    match &done.step.operation {
+        // The directory itself is one of the created ones
+        Operation::CreateDirectory { .. } => Ok(()),
+        Operation::CreateFile {
+            path,
+            overwrite: false,
+            ..
+        } => std::fs::remove_file(path).map_err(|error| fail(path, error)),
+        Operation::CreateFile { path, .. } => Err(cannot_restore(path, "Overwritten")),
+        Operation::Move { from, to } => {
+            let tracked = done.staged.then(|| tracked_entries(to)).flatten();
+            move_back(options, to, from)?;
+            match tracked {
+                Some(tracked) => tracked.stage_move(from).map_err(|error| fail(from, error)),
+                None => Ok(()),
+            }
+        }
+        Operation::Copy { to, .. } => remove_tree(options, to),
+        Operation::Delete { path, .. } => {
+            let Some(set_aside) = &done.set_aside else {
+                return Err(cannot_restore(path, "Deleted"));
+            };
+            move_back(options, &set_aside.path, path)?;
+            match &set_aside.tracked {
+                Some(tracked) => tracked.stage_restore().map_err(|error| fail(path, error)),
+                None => Ok(()),
+            }
+        }
+        Operation::Backup { path, backup, .. } => move_back(options, backup, path),
+        Operation::FixLink(link_fix) => rewrite_link(&link_fix.moved_link, &link_fix.target)
+            .map_err(|error| fail(&link_fix.moved_link, error)),
+        Operation::RemoveEmptyDirectory { path } => {
+            std::fs::create_dir_all(path).map_err(|error| fail(path, error))
+        }
+        Operation::Keep { .. } => Ok(()),
+    }
+}
+
+fn move_back(
+    options: &StandardOptions,
+    from: &Utf8Path,
+    to: &Utf8Path,
+) -> Result<(), Vec<WalkError>> {
+    match std::fs::rename(from, to) {
+        Err(error) if error.kind() == ErrorKind::CrossesDevices => move_tree(options, from, to),
+        result => result.map_err(|error| fail(from, error)),
+    }
+}
+
+fn cannot_restore(path: &Utf8Path, message: &str) -> Vec<WalkError> {
+    fail(
+        path,
+        std::io::Error::other(format!("{}, cannot be restored", message)),
+    )
+}
+
+fn find_existing_ancestor(path: &Utf8Path) -> Option<Utf8PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .find(|ancestor| ancestor.is_dir())
+        .map(Utf8Path::to_owned)
+}
+
+/// Errors if the file system changed since the plan was made,
+/// so that the step would no longer do what was confirmed.
+fn check_precondition(operation: &Operation) -> Result<(), Vec<WalkError>> {
+    let exists = |path: &Utf8Path| path.symlink_metadata().is_ok();
+    let changed = |path: &Utf8Path, kind, message: &str| {
+        Err(vec![WalkError {
+            path: path.to_owned(),
+            error: std::io::Error::new(kind, message),
+        }])
+    };
+    match operation {
+        Operation::CreateFile {
+            path,
+            overwrite: false,
+            ..
+        } if exists(path) => changed(
+            path,
+            ErrorKind::AlreadyExists,
+            "Appeared since it was checked",
+        ),
+        Operation::Move { from, .. } | Operation::Copy { from, .. } if !exists(from) => changed(
+            from,
+            ErrorKind::NotFound,
+            "Disappeared since it was checked",
+        ),
+        Operation::Move { to, .. } | Operation::Copy { to, .. } if exists(to) => changed(
+            to,
+            ErrorKind::AlreadyExists,
+            "Appeared since it was checked",
+        ),
+        Operation::Delete { path, .. } | Operation::Backup { path, .. } if !exists(path) => {
+            changed(
+                path,
+                ErrorKind::NotFound,
+                "Disappeared since it was checked",
+            )
+        }
+        _ => Ok(()),
+    }
+}
+
+fn carry_out(
+    options: &StandardOptions,
+    execute_options: &ExecuteOptions,
+    done: &mut Done,
+    warnings: &mut Vec<WalkError>,
+) -> Result<(), Vec<WalkError>> {
+    match &done.step.operation {
+        Operation::CreateDirectory { path } => {
+            done.created_directories = create_directories(path)?;
+        }
+        Operation::CreateFile { path, content, .. } => {
+            done.created_directories = create_parent_directory(path)?;
+            std::fs::write(path, content.as_deref().unwrap_or(""))
+                .map_err(|error| fail(path, error))?;
+            if git_enabled(execute_options.git, path) {
+                done.staged = stage_new(path).unwrap_or_else(|error| {
+                    warnings.push(fail(path, error).remove(0));
+                    false
+                });
+            }
+        }
+        Operation::Move { from, to } => {
+            let (from, to) = (from.clone(), to.clone());
+            move_entry(options, execute_options, &from, &to, done, warnings)?;
+        }
+        Operation::Copy { from, to } => {
+            done.created_directories = create_parent_directory(to)?;
+            done.summary = copy_tree(options, &execute_options.copy, from, to)?;
+            if execute_options.verify {
+                let checksums = verify_tree(from, to)?;
+                done.verified = true;
+                if execute_options.manifest {
+                    let manifest =
+                        write_manifest(to, &checksums).map_err(|error| fail(to, error))?;
+                    done.manifest = Some(manifest);
+                }
+            }
+        }
+        Operation::Delete { path, is_dir } => {
+            let tracked = git_enabled(execute_options.git, path)
+                .then(|| tracked_entries(path))
+                .flatten();
+            let set_aside = if execute_options.roll_back {
+                Some(set_aside(handle_cwd(path).as_ref())?)
+            } else if *is_dir {
+                remove_tree(options, handle_cwd(path).as_ref())?;
+                None
+            } else {
+                std::fs::remove_file(path).map_err(|error| fail(path, error))?;
+                None
+            };
+            done.staged = stage(path, tracked.as_ref().map(Tracked::stage_removal), warnings);
+            done.set_aside = set_aside.map(|set_aside| SetAside {
+                path: set_aside,
+                tracked: tracked.filter(|_| done.staged),
+            });
+        }
+        Operation::Backup { path, backup, .. } => {
+            let tracked = git_enabled(execute_options.git, path)
+                .then(|| tracked_entries(path))
+                .flatten();
+            done.created_directories = create_parent_directory(backup)?;
+            move_to_backup(options, path, backup)?;
+            done.staged = stage(
+                path,
+                tracked.map(|tracked| tracked.stage_removal()),
+                warnings,
+            );
+        }
+        Operation::FixLink(link_fix) => {
+            if let Err(error) = rewrite_link(&link_fix.moved_link, &link_fix.new_target) {
+                warnings.push(fail(&link_fix.moved_link, error).remove(0));
+            }
+        }
+        Operation::RemoveEmptyDirectory { path } => {
+            // Fails when entries which were not overwritten are left in it
+            let _ = std::fs::remove_dir(path);
+        }
+        Operation::Keep { .. } => {}
+    }
+    Ok(())
+}
+
+fn move_entry(
+    options: &StandardOptions,
+    execute_options: &ExecuteOptions,
+    from: &Utf8Path,
+    to: &Utf8Path,
+    done: &mut Done,
+    warnings: &mut Vec<WalkError>,
+) -> Result<(), Vec<WalkError>> {
+    // Read before anything is moved
+    let tracked = git_enabled(execute_options.git, from)
+        .then(|| tracked_entries(from))
+        .flatten();
+    done.created_directories = create_parent_directory(to)?;
+    let from = handle_cwd(from);
+    let from = from.as_ref();
+
+    let mut checksums = None;
+    match std::fs::rename(from, to) {
+        Err(error) if error.kind() == ErrorKind::CrossesDevices => {
+            if execute_options.verify {
+                checksums = Some(move_tree_verified(options, from, to)?);
+                done.verified = true;
+            } else {
+                move_tree(options, from, to)?;
+            }
+        }
+        result => result.map_err(|error| fail(from, error))?,
+    }
+    done.staged = stage(to, tracked.map(|tracked| tracked.stage_move(to)), warnings);
+    if execute_options.manifest {
+        // Renamed files are not copied, their checksums are only needed for the manifest
+        let checksums = match checksums {
+            Some(checksums) => checksums,
+            None => hash_tree(to)?,
+        };
+        let manifest = write_manifest(to, &checksums).map_err(|error| fail(to, error))?;
+        done.manifest = Some(manifest);
+    }
+    Ok(())
+}
+
+// Returns the created directories, deepest first
+fn create_parent_directory(path: &Utf8Path) -> Result<Vec<Utf8PathBuf>, Vec<WalkError>> {
+    match path.parent() {
+        Some(parent) if !parent.as_str().is_empty() => create_directories(parent),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Creates `directory` with its missing ancestors, returns the created ones, deepest first.
+fn create_directories(directory: &Utf8Path) -> Result<Vec<Utf8PathBuf>, Vec<WalkError>> {
+    let missing = directory
+        .ancestors()
+        .take_while(|ancestor| {
+            !ancestor.as_str().is_empty() && ancestor.symlink_metadata().is_err()
+        })
+        .map(Utf8Path::to_owned)
+        .collect();
+    std::fs::create_dir_all(directory).map_err(|error| fail(directory, error))?;
+    Ok(missing)
+}
+
+/// Renames the entry at `path` to a free hidden sibling like ".name.PID.0",
+/// returns the new path.
+fn set_aside(path: &Utf8Path) -> Result<Utf8PathBuf, Vec<WalkError>> {
+    let name = path.file_name().unwrap_or(path.as_str());
+    let aside = (0..)
+        .map(|attempt| path.with_file_name(format!(".{}.{}.{}", name, std::process::id(), attempt)))
+        .find(|aside| aside.symlink_metadata().is_err())
+        .unwrap();
+    std::fs::rename(path, &aside).map_err(|error| fail(path, error))?;
+    Ok(aside)
+}
+
+/// Whether the git index was updated, `staged` is `None` for untracked entries.
+fn stage(
+    path: &Utf8Path,
+    staged: Option<std::io::Result<()>>,
+    warnings: &mut Vec<WalkError>,
+) -> bool {
+    match staged {
+        Some(Ok(())) => true,
+        Some(Err(error)) => {
+            warnings.push(fail(path, error).remove(0));
+            false
+        }
+        None => false,
+    }
+}
+
+fn fail(path: &Utf8Path, error: std::io::Error) -> Vec<WalkError> {
+    vec![WalkError {
+        path: path.to_owned(),
+        error,
+    }]
+}
+
+/// Prints a line for each step which was carried out, then the errors.
+/// An entry which is deleted or backed up to write another one in its place is shown
+/// along with the written entry.
+pub fn print_report(options: &StandardOptions, report: &Report) {
+    let mut replaced = None;
+    for (index, done) in report.done.iter().enumerate() {
+        if replaced_by(done, report.done.get(index + 1)) {
+            replaced = Some(&done.step.operation);
+            continue;
+        }
+        if let Some(line) = describe(options, done, replaced.take()) {
+            message_success!(options, "{}", line);
+        }
+    }
+    for done in &report.restored {
+        if let Some(line) = describe_undone(done) {
+            message_success!(options, "{}", line);
+        }
+    }
+    let errors = report.warnings.iter().chain(&report.failed);
+    for error in errors.chain(&report.not_restored) {
+        eprintln!("{}", error);
+    }
+    if options.keep_going {
+        let summary = format!(
+            "{} done, {} failed",
+            report.carried_out(),
+            report.failed.len()
+        );
+        if report.failed.is_empty() {
+            message_success!(options, "{}", summary);
+        } else {
+            eprintln!("{}", summary);
+        }
+    }
+}
+
+fn describe_undone(done: &Done) -> Option<String> {
+    let line = match &done.step.operation {
+        Operation::Move { from, to } => {
+            format!("Moved back {} -> {}", path_string(to), path_string(from))
+        }
+        Operation::Backup { path, backup, .. } => format!(
+            "Moved back {} -> {}",
+            path_string(backup),
+            path_string(path)
+        ),
+        Operation::CreateDirectory { path } => {
+            format!("Removed {}", path_string(directory_path(path)))
+        }
+        Operation::CreateFile { path, .. } | Operation::Copy { to: path, .. } => {
+            format!("Removed {}", path_string(path))
+        }
+        Operation::FixLink(link_fix) => format!(
+            "Reverted {}: {}",
+            path_string(&link_fix.moved_link),
+            path_string(&link_fix.target)
+        ),
+        _ => return None,
+    };
+    Some(line)
+}
+
+/// Whether `done` makes way for `next`, which writes to the same path: the entries replaced
+/// by `--merge`, and the backups of replaced files or of entries replaced by moves and copies.
+fn replaced_by(done: &Done, next: Option<&Done>) -> bool {
+    let Some(next) = next else {
+        return false;
+    };
+    if !done.step.operation.makes_way_for(&next.step.operation) {
+        return false;
+    }
+    match (&done.step.operation, &next.step.operation) {
+        (Operation::Delete { .. }, _) => next.step.merged.is_some(),
+        (Operation::Backup { is_dir, .. }, Operation::CreateFile { .. }) => !is_dir,
+        (Operation::Backup { .. }, _) => true,
+        _ => false,
+    }
+}
+
+/// Describes `done`, `replaced` is the step which made way for it.
+fn describe(
+    options: &StandardOptions,
+    done: &Done,
+    replaced: Option<&Operation>,
+) -> Option<String> {
+    let existing_ancestor = if options.no_color {
+        None
+    } else {
+        done.existing_ancestor.as_deref()
+    };
+    if let Some(merged) = &done.step.merged {
+        let (_, path) = done.step.operation.written()?;
+        let path = if merged.is_dir {
+            path_string(directory_path(path))
+        } else {
+            path_string(path)
+        };
+        return Some(format!(
+            "{} {}",
+            (if replaced.is_some() { "E" } else { "N" }).color(COLOR),
+            path.color(COLOR)
+        ));
+    }
+    let note = done.step.resolution.note();
+    let backup = match replaced {
+        Some(Operation::Backup { backup, .. }) => format!(" (backup: {})", path_string(backup)),
+        _ => String::new(),
+    };
+    let staged = if done.staged { " (staged)" } else { "" };
+    let line = match &done.step.operation {
+        Operation::CreateDirectory { path } => format!(
+            "{} {}{}",
+            "N".color(COLOR),
+            color_new(&directory_path(path), existing_ancestor, COLOR),
+            note
+        ),
+        Operation::CreateFile {
+            path, overwrite, ..
+        } => format!(
+            "{} {}{}{}{}",
+            (if *overwrite || replaced.is_some() {
+                "E"
+            } else {
+                "N"
+            })
+            .color(COLOR),
+            color_new(path, existing_ancestor, COLOR),
+            note,
+            backup,
+            staged
+        ),
+        Operation::Move { from, to } => format!(
+            "{} {} -> {}{}{}{}{}",
+            "M".color(COLOR),
+            path_string(from).bright_red(),
+            color_new(to, existing_ancestor, COLOR),
+            verified_note(done),
+            note,
+            backup,
+            staged
+        ),
+        Operation::Copy { from, to } => format!(
+            "{} {} -> {}{}{}{}{}",
+            "C".color(COLOR),
+            path_string(from).bright_red(),
+            color_new(to, existing_ancestor, COLOR),
+            reflink_note(&done.summary),
+            verified_note(done),
+            note,
+            backup
+        ),
+        Operation::Delete { path, is_dir } => {
+            format!("{}{}{}", deleted(path, *is_dir), note, staged)
+        }
+        Operation::Backup {
+            path,
+            backup,
+            is_dir,
+        } => format!(
+            "{}{} (backup: {}){}",
+            deleted(path, *is_dir),
+            note,
+            path_string(backup),
+            staged
+        ),
+        Operation::FixLink(link_fix) => format!(
+            "{} {}: {} -> {}",
+            "L".color(COLOR),
+            path_string(&link_fix.moved_link),
+            path_string(&link_fix.target).bright_red(),
+            path_string(&link_fix.new_target).color(COLOR)
+        ),
+        Operation::RemoveEmptyDirectory { .. } => return None,
+        Operation::Keep { path, reason } => match reason {
+            KeepReason::AlreadyLocated { source } => {
+                format!("\"{}\" is already located at \"{}\"", source, path)
+            }
+            KeepReason::Existing { is_dir } => format!(
+                "{} \"{}\" already exists",
+                if *is_dir { "Directory" } else { "File" },
+                path
+            ),
+            KeepReason::Skipped {
+                source: Some(source),
+            } => format!(
+                "Skipped {} -> {}{}",
+                path_string(source),
+                path_string(path),
+                note
+            ),
+            KeepReason::Skipped { source: None } => {
+                format!("Skipped {}{}", path_string(path), note)
+            }
+        },
+    };
+    Some(line)
+}
+
+fn deleted(path: &Utf8Path, is_dir: bool) -> colored::ColoredString {
+    let path = if is_dir {
+        directory_path(path).to_string()
+    } else {
+        path_string(path)
+    };
+    format!("D {}", path).bright_red()
+}
+
+fn verified_note(done: &Done) -> String {
+    match (&done.manifest, done.verified) {
+        (Some(manifest), true) => format!(" (verified, checksums in {})", path_string(manifest)),
+        (Some(manifest), false) => format!(" (checksums in {})", path_string(manifest)),
+        (None, true) => " (verified)".to_owned(),
+        (None, false) => String::new(),
+    }
+}
+
+fn reflink_note(summary: &CopySummary) -> String {
+    if summary.reflinked_files == 0 {
+        String::new()
+    } else if summary.reflinked_files == summary.files {
+        " (reflinked)".to_owned()
+    } else {
+        format!(
+            " ({} of {} files reflinked)",
+            summary.reflinked_files, summary.files
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execute_rolls_back_moves() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        std::fs::create_dir_all(root.join("dest")).unwrap();
+        for path in ["a", "b"] {
+            std::fs::write(root.join(path), path).unwrap();
+        }
+        let mut plan = Plan::default();
+        for name in ["a", "b", "missing"] {
+            plan.push(Operation::Move {
+                from: root.join(name),
+                to: root.join("dest").join(name),
+            });
+        }
+        let execute_options = ExecuteOptions {
+            roll_back: true,
+            ..Default::default()
+        };
+        let report = execute(&StandardOptions::quiet(), &execute_options, plan);
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.done.is_empty());
+        assert!(report.not_restored.is_empty());
+        let restored: Vec<_> = report
+            .restored
+            .iter()
+            .map(|done| match &done.step.operation {
+                Operation::Move { from, .. } => from.strip_prefix(root).unwrap().to_string(),
+                _ => "other".to_owned(),
+            })
+            .collect();
+        assert_eq!(restored, ["b", "a"]);
+        assert_eq!(std::fs::read_to_string(root.join("a")).unwrap(), "a");
+        assert_eq!(std::fs::read_to_string(root.join("b")).unwrap(), "b");
+        assert!(!root.join("dest/a").exists());
+    }
+
+    #[test]
+    fn test_execute_rolls_back_replacements_and_created_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        std::fs::create_dir_all(root.join("dest")).unwrap();
+        for path in ["a", "b", "dest/a"] {
+            std::fs::write(root.join(path), path).unwrap();
+        }
+        let mut plan = Plan::default();
+        plan.push(Operation::CreateDirectory {
+            path: root.join("new/sub"),
+        });
+        plan.push(Operation::Delete {
+            path: root.join("dest/a"),
+            is_dir: false,
+        });
+        plan.push(Operation::Move {
+            from: root.join("a"),
+            to: root.join("dest/a"),
+        });
+        plan.push(Operation::Move {
+            from: root.join("b"),
+            to: root.join("other/b"),
+        });
+        plan.push(Operation::Move {
+            from: root.join("missing"),
+            to: root.join("dest/missing"),
+        });
+        let execute_options = ExecuteOptions {
+            roll_back: true,
+            ..Default::default()
+        };
+        let report = execute(&StandardOptions::quiet(), &execute_options, plan);
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.done.is_empty());
+        assert!(report.not_restored.is_empty());
+        assert_eq!(std::fs::read_to_string(root.join("a")).unwrap(), "a");
+        assert_eq!(std::fs::read_to_string(root.join("b")).unwrap(), "b");
+        assert_eq!(
+            std::fs::read_to_string(root.join("dest/a")).unwrap(),
+            "dest/a"
+        );
+        let mut names: Vec<_> = root
+            .read_dir_utf8()
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["a", "b", "dest"]);
+        assert_eq!(root.join("dest").read_dir_utf8().unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_execute_deletes_replaced_entries_after_success() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        std::fs::create_dir_all(root.join("dest/a")).unwrap();
+        std::fs::write(root.join("dest/a/old"), "old").unwrap();
+        std::fs::write(root.join("a"), "a").unwrap();
+        let mut plan = Plan::default();
+        plan.push(Operation::Delete {
+            path: root.join("dest/a"),
+            is_dir: true,
+        });
+        plan.push(Operation::Move {
+            from: root.join("a"),
+            to: root.join("dest/a"),
+        });
+        let execute_options = ExecuteOptions {
+            roll_back: true,
+            ..Default::default()
+        };
+        let report = execute(&StandardOptions::quiet(), &execute_options, plan);
+        assert!(report.failed.is_empty());
+        assert!(report.warnings.is_empty());
+        assert_eq!(std::fs::read_to_string(root.join("dest/a")).unwrap(), "a");
+        assert_eq!(root.join("dest").read_dir_utf8().unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_validate_checks_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        let locked = root.join("a");
+        std::fs::create_dir_all(locked.join("inner")).unwrap();
+        std::fs::write(locked.join("file"), "file").unwrap();
+        // Root may write to any directory, but not to an immutable one
+        let is_root = rustix::process::geteuid().is_root();
+        if is_root && !chattr("+i", &locked) {
+            eprintln!("Skipped: \"chattr +i\" is not available for {}", locked);
+            return;
+        }
+        if !is_root {
+            std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o555)).unwrap();
+        }
+        let delete = || {
+            let mut plan = Plan::default();
+            plan.push(Operation::Delete {
+                path: root.join("a"),
+                is_dir: true,
+            });
+            plan
+        };
+        let validated = delete().validate(false);
+        let mut kept_going = delete();
+        let kept_going_validated = kept_going.validate(true);
+        if is_root {
+            assert!(chattr("-i", &locked));
+        } else {
+            std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let Err(Error::Paths(errors)) = validated else {
+            panic!("Expected the locked directory to fail");
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, locked);
+        // With keep going, the failing deletion is left out instead
+        assert!(kept_going_validated.is_ok());
+        assert!(kept_going.steps.is_empty());
+        assert_eq!(kept_going.failed.len(), 1);
+        assert_eq!(kept_going.failed[0].path, locked);
+    }
+
+    // Whether the flags of `path` could be changed
+    #[cfg(unix)]
+    fn chattr(flags: &str, path: &Utf8Path) -> bool {
+        std::process::Command::new("chattr")
+            .args([flags, path.as_str()])
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{Dir, Entry};
+
+/// A file attribute `cop --preserve` can keep on copies.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attribute {
+    /// Permission bits
+    Mode,
+    /// Access and modification times
+    Timestamps,
+    /// Extended attributes
+    Xattr,
+    /// User and group, usually requires root
+    Owner,
+    /// All of the above, keeping the owner only where permitted
+    All,
+}
+
+/// The attributes of the originals applied to copied files, directories and symlinks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preserve {
+    pub mode: bool,
+    pub timestamps: bool,
+    pub xattr: bool,
+    pub owner: bool,
+    /// Whether failing to set the owner is an error rather than skipped.
+    pub require_owner: bool,
+}
+
+impl Preserve {
+    /// Only the permission bits, like `std::fs::copy`.
+    pub const MODE: Preserve = Preserve {
+        mode: true,
+        timestamps: false,
+        xattr: false,
+        owner: false,
+        require_owner: false,
+    };
+
+    /// The permission bits and timestamps, kept by moves across file systems.
+    pub const MOVE: Preserve = Preserve {
+        mode: true,
+        timestamps: true,
+        xattr: false,
+        owner: false,
+        require_owner: false,
+    };
+
+    pub const ALL: Preserve = Preserve {
+        mode: true,
+        timestamps: true,
+        xattr: true,
+        owner: true,
+        require_owner: false,
+    };
+
+    /// The permission bits and the given `attributes`.
+    pub fn with(attributes: &[Attribute]) -> Self {
+        let mut preserve = Preserve::MODE;
+        for attribute in attributes {
+            match attribute {
+                Attribute::Mode => preserve.mode = true,
+                Attribute::Timestamps => preserve.timestamps = true,
+                Attribute::Xattr => preserve.xattr = true,
+                Attribute::Owner => {
+                    preserve.owner = true;
+                    preserve.require_owner = true;
+                }
+                Attribute::All => {
+                    preserve = Preserve {
+                        require_owner: preserve.require_owner,
+                        ..Preserve::ALL
+                    }
+                }
+            }
+        }
+        preserve
+    }
+
+    /// Applies the attributes of `entry` to its copy `name` in `destination`.
+    /// Directories are done after their contents, so that their timestamps
+    /// stay unchanged and a read-only mode doesn't prevent creating the contents.
+    #[cfg(unix)]
+    pub(crate) fn apply(
+        &self,
+        entry: &Entry,
+        destination: &Dir,
+        name: &str,
+    ) -> std::io::Result<()> {
+        use rustix::fs::{AtFlags, Mode, Timespec, Timestamps};
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = &entry.metadata;
+        // Changing the owner can clear the setuid and setgid bits, so it goes first
+        if self.owner {
+            let path = destination.path().join(name);
+            let result =
+                std::os::unix::fs::lchown(path, Some(metadata.uid()), Some(metadata.gid()));
+            if self.require_owner {
+                result?;
+            } else {
+                ignore_unpermitted(result)?;
+            }
+        }
+        // The mode of symlinks cannot be changed and is never used
+        if self.mode && !metadata.is_symlink() {
+            rustix::fs::chmodat(
+                destination,
+                name,
+                Mode::from_raw_mode(metadata.mode()),
+                AtFlags::empty(),
+            )?;
+        }
+        if self.xattr {
+            copy_xattrs(entry, &destination.path().join(name))?;
+        }
+        if self.timestamps {
+            let times = Timestamps {
+                last_access: Timespec {
+                    tv_sec: metadata.atime(),
+                    tv_nsec: metadata.atime_nsec() as _,
+                },
+                last_modification: Timespec {
+                    tv_sec: metadata.mtime(),
+                    tv_nsec: metadata.mtime_nsec() as _,
+                },
+            };
+            rustix::fs::utimensat(destination, name, &times, AtFlags::SYMLINK_NOFOLLOW)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn apply(
+        &self,
+        entry: &Entry,
+        destination: &Dir,
+        name: &str,
+    ) -> std::io::Result<()> {
+        let path = destination.path().join(name);
+        if self.mode && !entry.metadata.is_symlink() {
+            std::fs::set_permissions(&path, entry.metadata.permissions())?;
+        }
+        if self.timestamps && entry.metadata.is_file() {
+            let times = std::fs::FileTimes::new()
+                .set_accessed(entry.metadata.accessed()?)
+                .set_modified(entry.metadata.modified()?);
+            std::fs::File::options()
+                .write(true)
+                .open(&path)?
+                .set_times(times)?;
+        }
+        Ok(())
+    }
+}
+
+/// Extended attributes, and owners unless asked for by name, are kept where possible,
+/// the copy succeeds without them when the user may not set them or the destination
+/// doesn't support them.
+#[cfg(unix)]
+fn ignore_unpermitted(result: std::io::Result<()>) -> std::io::Result<()> {
+    match result {
+        Err(error)
+            if matches!(
+                error.kind(),
+                std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::Unsupported
+            ) =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn copy_xattrs(entry: &Entry, to: &camino::Utf8Path) -> std::io::Result<()> {
+    let from = &entry.path;
+    let mut names = vec![0; rustix::fs::llistxattr(from.as_std_path(), &mut [])?];
+    let length = rustix::fs::llistxattr(from.as_std_path(), &mut names)?;
+    // `c_char` is signed on some platforms
+    #[allow(clippy::unnecessary_cast)]
+    let names: Vec<u8> = names[..length].iter().map(|&byte| byte as u8).collect();
+    for name in names
+        .split(|&byte| byte == 0)
+        .filter(|name| !name.is_empty())
+    {
+        let name = <std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(name);
+        let mut value = vec![0; rustix::fs::lgetxattr(from.as_std_path(), name, &mut [])?];
+        let length = rustix::fs::lgetxattr(from.as_std_path(), name, &mut value)?;
+        // Some namespaces, like `security`, can only be written with privileges
+        ignore_unpermitted(
+            rustix::fs::lsetxattr(
+                to.as_std_path(),
+                name,
+                &value[..length],
+                rustix::fs::XattrFlags::empty(),
+            )
+            .map_err(std::io::Error::from),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn copy_xattrs(_: &Entry, _: &camino::Utf8Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_requires_named_owner() {
+        assert!(!Preserve::with(&[Attribute::All]).require_owner);
+        assert!(Preserve::with(&[Attribute::Owner]).require_owner);
+        assert!(Preserve::with(&[Attribute::Owner, Attribute::All]).require_owner);
+        assert!(!Preserve::with(&[Attribute::Mode]).owner);
+    }
+}
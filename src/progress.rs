@@ -0,0 +1,173 @@
+use std::{
+    io::{IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{format_bytes, StandardOptions};
+
+// Operations finishing faster than this never show progress
+const THRESHOLD: Duration = Duration::from_secs(1);
+const REFRESH_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A single progress line on `stderr` for long running operations,
+/// shown only when `stderr` is a terminal and `--silent` is not used.
+pub struct Progress {
+    enabled: bool,
+    verb: &'static str,
+    start: Instant,
+    last_print: Option<Instant>,
+    entries: u64,
+    bytes: u64,
+    // The total entries and bytes when they are known in advance
+    totals: Option<(u64, u64)>,
+}
+
+impl Progress {
+    /// Progress of deleting entries, counted as they are deleted without a total or ETA.
+    pub fn deleting(options: &StandardOptions) -> Self {
+        Progress::new(options, "Deleting")
+    }
+
+    /// Progress of copying entries, the ETA is based on the number of bytes.
+    /// `totals` counts the entries and bytes, only when the progress is shown.
+    pub fn copying(options: &StandardOptions, totals: impl FnOnce() -> (u64, u64)) -> Self {
+        let mut progress = Progress::new(options, "Copying");
+        if progress.enabled {
+            progress.totals = Some(totals());
+        }
+        progress
+    }
+
+    fn new(options: &StandardOptions, verb: &'static str) -> Self {
+        Progress {
+            enabled: !options.silent && std::io::stderr().is_terminal(),
+            verb,
+            start: Instant::now(),
+            last_print: None,
+            entries: 0,
+            bytes: 0,
+            totals: None,
+        }
+    }
+
+    pub fn advance(&mut self, bytes: u64) {
+        self.entries += 1;
+        self.bytes += bytes;
+        if !self.enabled {
+            return;
+        }
+        let now = Instant::now();
+        let elapsed = now - self.start;
+        if elapsed < THRESHOLD
+            || self
+                .last_print
+                .is_some_and(|last_print| now - last_print < REFRESH_INTERVAL)
+        {
+            return;
+        }
+        self.last_print = Some(now);
+        eprint!("\r\x1b[2K{}", self.line(elapsed));
+        std::io::stderr().flush().unwrap();
+    }
+
+    fn line(&self, elapsed: Duration) -> String {
+        let throughput = format_bytes((self.bytes as f64 / elapsed.as_secs_f64()) as u64);
+        match self.totals {
+            Some((total_entries, total_bytes)) => format!(
+                "{} {}/{} entries, {}/{}, {}/s, ETA {}",
+                self.verb,
+                self.entries,
+                total_entries,
+                format_bytes(self.bytes),
+                format_bytes(total_bytes),
+                throughput,
+                format_duration(estimate_remaining(elapsed, self.bytes, total_bytes))
+            ),
+            None => format!(
+                "{} {} entries, {}, {}/s",
+                self.verb,
+                self.entries,
+                format_bytes(self.bytes),
+                throughput
+            ),
+        }
+    }
+
+    /// Clears the progress line so that it doesn't mix with other output.
+    pub fn finish(&mut self) {
+        if self.last_print.take().is_some() {
+            eprint!("\r\x1b[2K");
+            std::io::stderr().flush().unwrap();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// The time left to reach `total` when `done` took `elapsed`, assuming a steady rate.
+fn estimate_remaining(elapsed: Duration, done: u64, total: u64) -> Duration {
+    if done == 0 || total <= done {
+        return Duration::ZERO;
+    }
+    elapsed.mul_f64((total - done) as f64 / done as f64)
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 3600 {
+        format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60)
+    } else if seconds >= 60 {
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        let format = |seconds| format_duration(std::time::Duration::from_secs(seconds));
+        assert_eq!(format(0), "0s");
+        assert_eq!(format(59), "59s");
+        assert_eq!(format(61), "1m01s");
+        assert_eq!(format(3599), "59m59s");
+        assert_eq!(format(3600 * 25 + 120), "25h02m");
+    }
+
+    #[test]
+    fn test_estimate_remaining() {
+        let elapsed = std::time::Duration::from_secs(10);
+        let estimate = |done, total| estimate_remaining(elapsed, done, total).as_secs();
+        assert_eq!(estimate(25, 100), 30);
+        assert_eq!(estimate(50, 100), 10);
+        assert_eq!(estimate(0, 100), 0);
+        assert_eq!(estimate(100, 100), 0);
+        assert_eq!(estimate(120, 100), 0);
+    }
+
+    #[test]
+    fn test_line() {
+        let elapsed = Duration::from_secs(10);
+        let mut progress = Progress::new(&StandardOptions::quiet(), "Copying");
+        progress.entries = 25;
+        progress.bytes = 2_500;
+        progress.totals = Some((100, 10_000));
+        assert_eq!(
+            progress.line(elapsed),
+            "Copying 25/100 entries, 2.5 KB/10.0 KB, 250 B/s, ETA 30s"
+        );
+        progress.verb = "Deleting";
+        progress.totals = None;
+        assert_eq!(
+            progress.line(elapsed),
+            "Deleting 25 entries, 2.5 KB, 250 B/s"
+        );
+    }
+}
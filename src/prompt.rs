@@ -0,0 +1,105 @@
+use std::io::{BufRead, Write};
+
+use crate::FAILURE;
+
+#[cfg(not(windows))]
+const TERMINAL_PATH: &str = "/dev/tty";
+#[cfg(windows)]
+const TERMINAL_PATH: &str = "CONIN$";
+
+// Allows the integration tests to answer prompts without a real terminal. Only debug builds,
+// which the tests run, read it, so that release builds always ask the terminal.
+#[cfg(debug_assertions)]
+const TERMINAL_PATH_OVERRIDE: &str = "HUMAN_UTILS_TTY";
+
+/// The answer assumed when the user confirms a prompt with an empty line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefaultAnswer {
+    Yes,
+    No,
+}
+
+impl DefaultAnswer {
+    /// Prompts which lead to irreversible changes default to "no".
+    pub const DESTRUCTIVE: DefaultAnswer = DefaultAnswer::No;
+
+    pub fn hint(self) -> &'static str {
+        match self {
+            DefaultAnswer::Yes => "[Y/n]",
+            DefaultAnswer::No => "[y/N]",
+        }
+    }
+
+    fn accepts(self, input: &str) -> bool {
+        let input = input.trim().to_lowercase();
+        if input.is_empty() {
+            self == DefaultAnswer::Yes
+        } else {
+            input.starts_with('y')
+        }
+    }
+}
+
+/// Prints the answer hint and reads the answer from the terminal,
+/// not from `stdin`, which might be a pipe or a file.
+/// Exits if the answer is not a yes, or if there is no terminal to ask.
+pub fn confirm_or_exit(default: DefaultAnswer) {
+    if !confirm(default) {
+        std::process::exit(FAILURE);
+    }
+}
+
+/// Like `confirm_or_exit`, but returns whether the answer was a yes.
+/// Still exits if there is no terminal to ask.
+pub fn confirm(default: DefaultAnswer) -> bool {
+    print!("{}", default.hint());
+    std::io::stdout().flush().unwrap();
+    match read_answer() {
+        Ok(input) => default.accepts(&input),
+        Err(error) => exit_without_terminal(error),
+    }
+}
+
+/// A stronger confirmation, for changes which lose work that exists nowhere else.
+/// Returns whether exactly `word` was typed, exits if there is no terminal to ask.
+pub fn confirm_word(word: &str) -> bool {
+    print!("[type \"{}\"]", word);
+    std::io::stdout().flush().unwrap();
+    match read_answer() {
+        Ok(input) => input.trim() == word,
+        Err(error) => exit_without_terminal(error),
+    }
+}
+
+/// Like `confirm_word`, but exits unless `word` was typed.
+pub fn confirm_word_or_exit(word: &str) {
+    if !confirm_word(word) {
+        std::process::exit(FAILURE);
+    }
+}
+
+fn exit_without_terminal(error: std::io::Error) -> ! {
+    println!();
+    eprintln!(
+        "Error: Cannot ask for confirmation, no terminal is available ({}). Use --force to proceed without confirmation.",
+        error
+    );
+    std::process::exit(FAILURE);
+}
+
+fn read_answer() -> std::io::Result<String> {
+    let terminal = std::fs::File::open(terminal_path())?;
+    let mut input = String::new();
+    std::io::BufReader::new(terminal).read_line(&mut input)?;
+    Ok(input)
+}
+
+#[cfg(debug_assertions)]
+fn terminal_path() -> std::ffi::OsString {
+    std::env::var_os(TERMINAL_PATH_OVERRIDE).unwrap_or_else(|| TERMINAL_PATH.into())
+}
+
+#[cfg(not(debug_assertions))]
+fn terminal_path() -> std::ffi::OsString {
+    TERMINAL_PATH.into()
+}
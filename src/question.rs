@@ -0,0 +1,320 @@
+use camino::{Utf8Path, Utf8PathBuf};
+use colored::Colorize;
+
+use crate::{
+    confirm, confirm_word, directory_path, find_unsaved_work, path_string, print_path_summary,
+    summarize_for_prompt, summary_deadline, DefaultAnswer, HeldOpen, IncomingLink, LinkFix,
+    StandardOptions,
+};
+
+/// A confirmation `new`, `mov`, `cop` and `del` need before changing something.
+pub enum Question<'a> {
+    /// Delete `paths`, some of which might not exist, breaking the `incoming_links` into them
+    /// and pulling them from under the processes which hold them open
+    Delete {
+        paths: &'a [&'a Utf8Path],
+        incoming_links: &'a [IncomingLink],
+        held_open: &'a [HeldOpen],
+    },
+    /// Replace the existing entry at the single destination `path`,
+    /// moving paths which the `held_open` processes use
+    Replace {
+        path: &'a Utf8Path,
+        held_open: &'a [HeldOpen],
+    },
+    /// Overwrite the existing entries at `paths`, moving paths which the `held_open` processes use
+    Overwrite {
+        paths: &'a [Utf8PathBuf],
+        held_open: &'a [HeldOpen],
+    },
+    /// Rewrite relative symlinks inside the moved paths so that they still resolve
+    FixLinks { link_fixes: &'a [LinkFix] },
+    /// Retarget symlinks pointing into the moved paths to their new location
+    RetargetLinks { link_fixes: &'a [LinkFix] },
+    /// Move paths which running processes hold open, when nothing else is asked
+    MoveHeldOpen { held_open: &'a [HeldOpen] },
+}
+
+/// Answers `question` in the terminal, like the binaries do.
+/// With `--force`, warns about the symlinks which will no longer resolve and answers yes,
+/// without looking for git work which would be lost.
+pub fn ask_in_terminal(options: &StandardOptions, question: &Question) -> bool {
+    match question {
+        Question::Delete {
+            paths,
+            incoming_links,
+            held_open,
+        } => ask_to_delete(options, paths, incoming_links, held_open),
+        Question::Replace { path, held_open } => ask_to_replace(options, path, held_open),
+        Question::Overwrite { paths, held_open } => ask_to_overwrite(options, paths, held_open),
+        Question::FixLinks { link_fixes } => ask_to_fix_links(
+            "For the following relative symlinks...",
+            "...rewrite their targets so that they still resolve after the move? ",
+            link_fixes,
+        ),
+        Question::RetargetLinks { link_fixes } => ask_to_fix_links(
+            "For the following symlinks pointing into the moved paths...",
+            "...retarget them to the new location? ",
+            link_fixes,
+        ),
+        Question::MoveHeldOpen { held_open } => ask_to_move_held_open(options, held_open),
+    }
+}
+
+fn ask_to_delete(
+    options: &StandardOptions,
+    paths: &[&Utf8Path],
+    incoming_links: &[IncomingLink],
+    held_open: &[HeldOpen],
+) -> bool {
+    if options.force {
+        for link in incoming_links {
+            eprintln!(
+                "Warning: The symlink \"{}\" -> \"{}\" will no longer resolve.",
+                link.link, link.target
+            );
+        }
+        warn_held_open(held_open);
+        return true;
+    }
+
+    let losing_work = check_unsaved_work(paths);
+    print_incoming_links(incoming_links);
+    print_held_open(held_open);
+
+    let recent = if let [path] = paths {
+        ask_to_delete_single_path(options, path)
+    } else {
+        ask_to_delete_multiple_paths(options, paths)
+    };
+
+    // #[tested(rem_basic)]
+    recent.is_some_and(|recent| confirm_strictly(losing_work || recent, "delete"))
+}
+
+fn print_incoming_links(incoming_links: &[IncomingLink]) {
+    if incoming_links.is_empty() {
+        return;
+    }
+    println!("The following symlinks will no longer resolve...");
+    for link in incoming_links {
+        println!(
+            "{} -> {}",
+            path_string(&link.link),
+            path_string(&link.target).bright_red()
+        );
+    }
+}
+
+fn print_held_open(held_open: &[HeldOpen]) {
+    if held_open.is_empty() {
+        return;
+    }
+    println!("The following processes hold paths open...");
+    for held in held_open {
+        println!(
+            "{} {}: {} ({})",
+            held.pid,
+            held.name,
+            path_string(&held.path).bright_red(),
+            held.how.description()
+        );
+    }
+}
+
+fn warn_held_open(held_open: &[HeldOpen]) {
+    for held in held_open {
+        eprintln!(
+            "Warning: \"{}\" is in use by {} {} ({}).",
+            held.path,
+            held.pid,
+            held.name,
+            held.how.description()
+        );
+    }
+}
+
+fn ask_to_move_held_open(options: &StandardOptions, held_open: &[HeldOpen]) -> bool {
+    if options.force {
+        warn_held_open(held_open);
+        return true;
+    }
+    print_held_open(held_open);
+    print!("...move them anyway? ");
+    confirm(DefaultAnswer::DESTRUCTIVE)
+}
+
+// Returns whether `path` was modified recently, `None` if it doesn't exist
+fn ask_to_delete_single_path(options: &StandardOptions, path: &Utf8Path) -> Option<bool> {
+    match path.symlink_metadata() {
+        Ok(metadata) => {
+            let description = summarize_for_prompt(options, path, &metadata);
+            // #[tested(rem_basic)]
+            print!(
+                "Delete {} \"{}\"{}? ",
+                file_type(&metadata),
+                path,
+                description
+            );
+            Some(description.recent)
+        }
+        Err(error) => {
+            // #[tested(rem_no_existing::nonexistent_path_fails)]
+            eprintln!("Error for \"{}\": {}", path, error);
+            None
+        }
+    }
+}
+
+// Returns whether any of `paths` was modified recently, `None` if none of them exist
+fn ask_to_delete_multiple_paths(options: &StandardOptions, paths: &[&Utf8Path]) -> Option<bool> {
+    println!("For the following...");
+    let mut all_exist = true;
+    let mut some_exist = false;
+    let mut recent = false;
+    let deadline = summary_deadline();
+    for path in paths {
+        match path.symlink_metadata() {
+            Ok(metadata) => {
+                // #[tested(rem_multiple)]
+                recent |= print_path_summary(options, path, &metadata, deadline);
+                some_exist = true;
+            }
+            Err(error) => {
+                // #[tested(rem_no_existing)]
+                eprintln!("\"{}\" error: {}", path, error);
+                all_exist = false;
+            }
+        }
+    }
+    if !some_exist {
+        // #[tested(rem_no_existing)]
+        eprintln!("...no files or directories can be removed.");
+        return None;
+    }
+    // #[tested(rem_basic)]
+    print!(
+        "...delete all{}? ",
+        if all_exist { "" } else { " existing" }
+    );
+    Some(recent)
+}
+
+fn ask_to_replace(options: &StandardOptions, path: &Utf8Path, held_open: &[HeldOpen]) -> bool {
+    if options.force {
+        warn_held_open(held_open);
+        return true;
+    }
+    let losing_work = check_unsaved_work(&[path]);
+    print_held_open(held_open);
+    let metadata = path.symlink_metadata().unwrap();
+    let file_type = if metadata.is_dir() {
+        "Directory"
+    } else {
+        "File"
+    };
+    let description = summarize_for_prompt(options, path, &metadata);
+    print!(
+        "{} \"{}\"{} already exists, replace it? ",
+        file_type, path, description
+    );
+    confirm_strictly(losing_work || description.recent, "replace")
+}
+
+fn ask_to_overwrite(
+    options: &StandardOptions,
+    paths: &[Utf8PathBuf],
+    held_open: &[HeldOpen],
+) -> bool {
+    if options.force {
+        warn_held_open(held_open);
+        return true;
+    }
+    let losing_work = check_unsaved_work(paths);
+    print_held_open(held_open);
+    let recent = if let [path] = paths {
+        let metadata = path.symlink_metadata().unwrap();
+        let description = summarize_for_prompt(options, path, &metadata);
+        print!(
+            "Overwrite {} \"{}\"{}? ",
+            file_type(&metadata),
+            path,
+            description
+        );
+        description.recent
+    } else {
+        println!("For the following...");
+        let deadline = summary_deadline();
+        let mut recent = false;
+        for path in paths {
+            let metadata = path.symlink_metadata().unwrap();
+            recent |= print_path_summary(options, path, &metadata, deadline);
+        }
+        print!("...overwrite all? ");
+        recent
+    };
+    confirm_strictly(losing_work || recent, "overwrite")
+}
+
+fn ask_to_fix_links(before: &str, question: &str, link_fixes: &[LinkFix]) -> bool {
+    println!("{}", before);
+    for link_fix in link_fixes {
+        println!(
+            "{} -> {}",
+            path_string(&link_fix.link),
+            path_string(&link_fix.target)
+        );
+    }
+    print!("{}", question);
+    confirm(DefaultAnswer::Yes)
+}
+
+fn file_type(metadata: &std::fs::Metadata) -> &'static str {
+    if metadata.is_dir() {
+        "directory"
+    } else {
+        "file"
+    }
+}
+
+/// Lists the git repositories at or under `paths` with work which exists nowhere else,
+/// and says so when the search ran out of time. Returns whether there are any,
+/// so that the following prompt can ask for a stronger confirmation.
+fn check_unsaved_work<P: AsRef<Utf8Path>>(paths: &[P]) -> bool {
+    let deadline = summary_deadline();
+    let mut unsaved = Vec::new();
+    let mut truncated = false;
+    for path in paths {
+        let found = find_unsaved_work(path.as_ref(), deadline);
+        unsaved.extend(found.work);
+        truncated |= found.truncated;
+    }
+    if !unsaved.is_empty() {
+        println!("The following git repositories contain work which exists nowhere else...");
+        for work in &unsaved {
+            println!(
+                "{}: {}",
+                directory_path(&work.repository),
+                work.describe().bright_red()
+            );
+        }
+    }
+    if truncated {
+        println!(
+            "{}",
+            "Stopped looking for git repositories with unsaved work, there may be more."
+                .bright_yellow()
+        );
+    }
+    !unsaved.is_empty()
+}
+
+/// Asks to type out `word` instead of a plain yes with `strict`, for prompts
+/// about work which would be lost or which was modified within `--recent`.
+fn confirm_strictly(strict: bool, word: &str) -> bool {
+    if strict {
+        confirm_word(word)
+    } else {
+        confirm(DefaultAnswer::DESTRUCTIVE)
+    }
+}
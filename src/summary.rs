@@ -0,0 +1,257 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use colored::Colorize;
+
+use crate::{directory_path, format_bytes, path_string, summarize_tree_until, StandardOptions};
+
+/// How long a prompt may spend counting the contents of the paths it asks about
+const SUMMARY_BUDGET: Duration = Duration::from_millis(500);
+/// How many children of a directory are listed in prompts
+const LISTED_CHILDREN: usize = 5;
+
+/// When counting stops for a prompt, shared by all paths it asks about.
+pub fn summary_deadline() -> Instant {
+    Instant::now() + SUMMARY_BUDGET
+}
+
+/// The description of a path in a prompt.
+pub struct Description {
+    text: String,
+    /// Whether the path or any of its descendants was modified within `--recent`
+    pub recent: bool,
+}
+
+impl std::fmt::Display for Description {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Describes the size and age of `path` for prompts, like
+/// "3 files, 1 directory, 4.0 KB, newest modified 5 minutes ago".
+/// Counts which stopped at `deadline` are shown as lower bounds, like "≥3 files".
+/// Recent modifications are highlighted, naming the newest descendant of directories.
+pub fn describe_path(
+    options: &StandardOptions,
+    path: &Utf8Path,
+    metadata: &std::fs::Metadata,
+    deadline: Instant,
+) -> Description {
+    if !metadata.is_dir() {
+        let (modified, recent) = modification(options, "modified", metadata.modified().ok(), None);
+        return Description {
+            text: format!("{}, {}", format_bytes(metadata.len()), modified),
+            recent,
+        };
+    }
+    let summary = summarize_tree_until(path, deadline);
+    let at_least = if summary.truncated { "≥" } else { "" };
+    // The directory itself is not part of its contents
+    let directories = summary.directories.saturating_sub(1);
+    let mut parts = Vec::new();
+    if summary.files > 0 {
+        parts.push(format!(
+            "{}{}",
+            at_least,
+            count(summary.files, "file", "files")
+        ));
+    }
+    if directories > 0 {
+        parts.push(format!(
+            "{}{}",
+            at_least,
+            count(directories, "directory", "directories")
+        ));
+    }
+    let newest = summary
+        .newest_path
+        .as_ref()
+        .and_then(|newest| newest.strip_prefix(path).ok())
+        .filter(|newest| !newest.as_str().is_empty());
+    let empty = parts.is_empty() && !summary.truncated;
+    if empty {
+        parts.push("empty".to_owned());
+    } else {
+        parts.push(format!("{}{}", at_least, format_bytes(summary.bytes)));
+    }
+    let label = if empty { "modified" } else { "newest modified" };
+    let (modified, recent) = modification(options, label, summary.newest, newest);
+    parts.push(modified);
+    Description {
+        text: parts.join(", "),
+        recent,
+    }
+}
+
+// Like "modified 5 minutes ago: sub/file", highlighted within `--recent`
+fn modification(
+    options: &StandardOptions,
+    label: &str,
+    time: Option<SystemTime>,
+    newest: Option<&Utf8Path>,
+) -> (String, bool) {
+    let modified = format!("{} {}", label, format_age(time));
+    let window = Duration::from_secs(options.recent.saturating_mul(60));
+    let recent = time.is_some_and(|time| age(time) < window);
+    if !recent {
+        return (modified, false);
+    }
+    let modified = match newest {
+        Some(newest) => format!("{}: {}", modified, path_string(newest)),
+        None => modified,
+    };
+    (modified.bright_yellow().to_string(), true)
+}
+
+/// Prints `path` with its description, directories followed by their first children.
+/// Returns whether it was modified recently.
+pub fn print_path_summary(
+    options: &StandardOptions,
+    path: &Utf8Path,
+    metadata: &std::fs::Metadata,
+    deadline: Instant,
+) -> bool {
+    let description = describe_path(options, path, metadata, deadline);
+    if metadata.is_dir() {
+        println!("{} ({})", directory_path(path), description);
+        print_children(path);
+    } else {
+        println!("{} ({})", path_string(path), description);
+    }
+    description.recent
+}
+
+/// Prepares the prompt about the single `path`: directories are summarized on
+/// the lines before it, returns the description of a file to add to the question.
+pub fn summarize_for_prompt(
+    options: &StandardOptions,
+    path: &Utf8Path,
+    metadata: &std::fs::Metadata,
+) -> Description {
+    let deadline = summary_deadline();
+    if metadata.is_dir() {
+        let recent = print_path_summary(options, path, metadata, deadline);
+        Description {
+            text: String::new(),
+            recent,
+        }
+    } else {
+        let description = describe_path(options, path, metadata, deadline);
+        Description {
+            text: format!(" ({})", description),
+            recent: description.recent,
+        }
+    }
+}
+
+fn print_children(path: &Utf8Path) {
+    let Ok(entries) = path.read_dir_utf8() else {
+        return;
+    };
+    let mut children: Vec<Utf8PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let name = Utf8Path::new(entry.file_name());
+            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                directory_path(name)
+            } else {
+                name.to_owned()
+            }
+        })
+        .collect();
+    children.sort();
+    for child in children.iter().take(LISTED_CHILDREN) {
+        println!("  {}", path_string(child));
+    }
+    if children.len() > LISTED_CHILDREN {
+        println!("  ...and {} more", children.len() - LISTED_CHILDREN);
+    }
+}
+
+fn count(count: u64, singular: &str, plural: &str) -> String {
+    format!("{} {}", count, if count == 1 { singular } else { plural })
+}
+
+// Modification times in the future count as now
+fn age(time: SystemTime) -> Duration {
+    SystemTime::now().duration_since(time).unwrap_or_default()
+}
+
+fn format_age(time: Option<SystemTime>) -> String {
+    let Some(time) = time else {
+        return "at an unknown time".to_owned();
+    };
+    let seconds = age(time).as_secs();
+    match seconds {
+        0..60 => "just now".to_owned(),
+        60..3600 => ago(seconds / 60, "minute"),
+        3600..86400 => ago(seconds / 3600, "hour"),
+        _ => ago(seconds / 86400, "day"),
+    }
+}
+
+fn ago(count: u64, unit: &str) -> String {
+    format!(
+        "{} {}{} ago",
+        count,
+        unit,
+        if count == 1 { "" } else { "s" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_tree_until() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        for path in ["a/x", "a/sub/y"] {
+            std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            std::fs::write(root.join(path), path).unwrap();
+        }
+        let later = std::time::Instant::now() + std::time::Duration::from_secs(60);
+        let summary = summarize_tree_until(&root.join("a"), later);
+        assert!(!summary.truncated);
+        assert_eq!(
+            (summary.files, summary.directories, summary.bytes),
+            (2, 2, 10)
+        );
+        let expired = summarize_tree_until(&root.join("a"), std::time::Instant::now());
+        assert!(expired.truncated);
+        assert_eq!(expired.files, 0);
+        assert!(describe_path(
+            &StandardOptions::quiet(),
+            &root.join("a"),
+            &root.join("a").metadata().unwrap(),
+            std::time::Instant::now()
+        )
+        .to_string()
+        .starts_with("≥0 B"));
+    }
+
+    #[test]
+    fn test_describe_path_marks_lower_bounds() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        for path in ["a/x", "a/sub/y"] {
+            std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            std::fs::write(root.join(path), path).unwrap();
+        }
+        let (path, metadata) = (root.join("a"), root.join("a").metadata().unwrap());
+        let describe = |deadline| {
+            describe_path(&StandardOptions::quiet(), &path, &metadata, deadline).to_string()
+        };
+        let later = std::time::Instant::now() + std::time::Duration::from_secs(60);
+        assert_eq!(
+            describe(later),
+            "2 files, 1 directory, 10 B, newest modified just now"
+        );
+        assert_eq!(
+            describe(std::time::Instant::now()),
+            "≥0 B, newest modified just now"
+        );
+    }
+}
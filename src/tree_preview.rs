@@ -0,0 +1,366 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use colored::Colorize;
+
+use crate::{absolute_path, Report, StandardOptions};
+
+// Directories which are created, moved or deleted are listed this many levels deep
+const EXPANDED_DEPTH: usize = 2;
+const MAX_LISTED_CHILDREN: usize = 16;
+
+enum Operation {
+    CreateDirectory(Utf8PathBuf),
+    CreateFile(Utf8PathBuf),
+    Delete(Utf8PathBuf),
+    Rename(Utf8PathBuf, Utf8PathBuf),
+    Copy(Utf8PathBuf, Utf8PathBuf),
+}
+
+/// Records the operations performed during `--dry-run --tree`
+/// and prints the affected file tree before and after them.
+pub struct TreePreview {
+    enabled: bool,
+    operations: Vec<Operation>,
+}
+
+impl TreePreview {
+    pub fn new(options: &StandardOptions) -> Self {
+        TreePreview {
+            enabled: options.dry_run && options.tree,
+            operations: Vec::new(),
+        }
+    }
+
+    pub fn create_directory(&mut self, path: &Utf8Path) {
+        self.record(|| Operation::CreateDirectory(absolute_path(path)));
+    }
+
+    pub fn create_file(&mut self, path: &Utf8Path) {
+        self.record(|| Operation::CreateFile(absolute_path(path)));
+    }
+
+    pub fn delete(&mut self, path: &Utf8Path) {
+        self.record(|| Operation::Delete(absolute_path(path)));
+    }
+
+    pub fn rename(&mut self, from: &Utf8Path, to: &Utf8Path) {
+        self.record(|| Operation::Rename(absolute_path(from), absolute_path(to)));
+    }
+
+    pub fn create_copy(&mut self, from: &Utf8Path, to: &Utf8Path) {
+        self.record(|| Operation::Copy(absolute_path(from), absolute_path(to)));
+    }
+
+    /// Records the steps of `report` which change the file system.
+    pub fn add_report(&mut self, report: &Report) {
+        for done in &report.done {
+            match &done.step.operation {
+                crate::Operation::CreateDirectory { path } => self.create_directory(path),
+                crate::Operation::CreateFile { path, .. } => self.create_file(path),
+                crate::Operation::Move { from, to } => self.rename(from, to),
+                crate::Operation::Copy { from, to } => self.create_copy(from, to),
+                crate::Operation::Delete { path, .. } => self.delete(path),
+                crate::Operation::Backup { path, backup, .. } => self.rename(path, backup),
+                _ => {}
+            }
+        }
+    }
+
+    fn record(&mut self, operation: impl FnOnce() -> Operation) {
+        if self.enabled {
+            self.operations.push(operation());
+        }
+    }
+
+    /// Prints the trees, new and moved entries use the given `color`.
+    pub fn print(&self, color: colored::Color) {
+        if !self.enabled || self.operations.is_empty() {
+            return;
+        }
+        let root = self.common_root();
+        let mut before = Node::load(&root, &self.affected_paths());
+        let mut after = before.clone();
+        let mut deleted = BTreeSet::new();
+        for operation in &self.operations {
+            apply(&root, operation, &mut before, &mut after, &mut deleted);
+        }
+        let label = root_label(&root);
+        println!("Before:");
+        before.print(&label, color);
+        println!("After:");
+        after.print(&label, color);
+    }
+
+    fn affected_paths(&self) -> Vec<&Utf8Path> {
+        self.operations
+            .iter()
+            .flat_map(|operation| match operation {
+                Operation::CreateDirectory(path)
+                | Operation::CreateFile(path)
+                | Operation::Delete(path) => vec![path.as_path()],
+                Operation::Rename(from, to) | Operation::Copy(from, to) => {
+                    vec![from.as_path(), to.as_path()]
+                }
+            })
+            .collect()
+    }
+
+    fn common_root(&self) -> Utf8PathBuf {
+        let mut parents = self
+            .affected_paths()
+            .into_iter()
+            .map(|path| path.parent().unwrap_or(path));
+        let mut root = parents.next().unwrap().to_owned();
+        for parent in parents {
+            while !parent.starts_with(&root) {
+                root.pop();
+            }
+        }
+        while !root.is_dir() && root.pop() {}
+        root
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Mark {
+    New,
+    Moved,
+    Replaced,
+    Deleted,
+}
+
+#[derive(Clone)]
+struct Node {
+    is_dir: bool,
+    mark: Option<Mark>,
+    children: BTreeMap<String, Node>,
+    expanded: bool,
+    omitted: usize,
+}
+
+impl Node {
+    fn new(is_dir: bool, mark: Option<Mark>) -> Self {
+        Node {
+            is_dir,
+            mark,
+            children: BTreeMap::new(),
+            expanded: false,
+            omitted: 0,
+        }
+    }
+
+    fn load(root: &Utf8Path, affected_paths: &Vec<&Utf8Path>) -> Self {
+        let mut node = Node::new(true, None);
+        node.expand(root, 0);
+        for path in affected_paths {
+            node.load_path(root, path.strip_prefix(root).unwrap());
+        }
+        node
+    }
+
+    fn load_path(&mut self, directory: &Utf8Path, relative_path: &Utf8Path) {
+        let mut components = relative_path.components();
+        let Some(name) = components.next() else {
+            return;
+        };
+        let name = name.as_str();
+        let path = directory.join(name);
+        if !self.children.contains_key(name) {
+            let Ok(metadata) = path.symlink_metadata() else {
+                return;
+            };
+            self.omitted = self.omitted.saturating_sub(1);
+            self.children
+                .insert(name.to_owned(), Node::new(metadata.is_dir(), None));
+        }
+        let child = self.children.get_mut(name).unwrap();
+        if !child.is_dir {
+            return;
+        }
+        let rest = components.as_path();
+        if rest.as_str().is_empty() {
+            child.expand(&path, EXPANDED_DEPTH);
+        } else {
+            child.expand(&path, 0);
+            child.load_path(&path, rest);
+        }
+    }
+
+    fn expand(&mut self, path: &Utf8Path, depth: usize) {
+        if !self.expanded {
+            self.expanded = true;
+            let Ok(entries) = path.read_dir_utf8() else {
+                return;
+            };
+            let mut entries: Vec<_> = entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let is_dir = entry.file_type().ok()?.is_dir();
+                    Some((entry.file_name().to_owned(), is_dir))
+                })
+                .collect();
+            entries.sort();
+            self.omitted = entries.len().saturating_sub(MAX_LISTED_CHILDREN);
+            for (name, is_dir) in entries.into_iter().take(MAX_LISTED_CHILDREN) {
+                self.children.insert(name, Node::new(is_dir, None));
+            }
+        }
+        if depth > 1 {
+            for (name, child) in &mut self.children {
+                if child.is_dir {
+                    child.expand(&path.join(name), depth - 1);
+                }
+            }
+        }
+    }
+
+    fn get_mut(&mut self, relative_path: &Utf8Path) -> Option<&mut Node> {
+        relative_path
+            .components()
+            .try_fold(self, |node, name| node.children.get_mut(name.as_str()))
+    }
+
+    fn remove(&mut self, relative_path: &Utf8Path) -> Option<Node> {
+        let parent = self.get_mut(relative_path.parent()?)?;
+        parent.children.remove(relative_path.file_name()?)
+    }
+
+    fn mark(&mut self, relative_path: &Utf8Path, mark: Mark) {
+        if let Some(node) = self.get_mut(relative_path) {
+            node.mark = Some(mark);
+        }
+    }
+
+    /// Inserts `node` at `relative_path`, creating missing directories on the way.
+    /// Returns whether an existing entry was replaced.
+    fn insert(&mut self, relative_path: &Utf8Path, node: Node) -> bool {
+        let mut parent = self;
+        if let Some(parent_path) = relative_path.parent() {
+            for name in parent_path.components() {
+                parent = parent
+                    .children
+                    .entry(name.as_str().to_owned())
+                    .or_insert_with(|| Node::new(true, Some(Mark::New)));
+            }
+        }
+        parent
+            .children
+            .insert(relative_path.file_name().unwrap().to_owned(), node)
+            .is_some()
+    }
+
+    fn print(&self, label: &str, color: colored::Color) {
+        println!("{}", self.line("📂", label, None));
+        self.print_children("", self.mark_color(color), color);
+    }
+
+    fn print_children(
+        &self,
+        indent: &str,
+        marked_color: Option<colored::Color>,
+        color: colored::Color,
+    ) {
+        let count = self.children.len();
+        for (i, (name, child)) in self.children.iter().enumerate() {
+            let is_last = i + 1 == count && self.omitted == 0;
+            let child_color = child.mark_color(color).or(marked_color);
+            println!(
+                "{}{} {}",
+                indent,
+                if is_last { "└" } else { "├" },
+                child.line(if child.is_dir { "📂" } else { "📜" }, name, child_color)
+            );
+            let child_indent = format!("{}{} ", indent, if is_last { " " } else { "│" });
+            child.print_children(&child_indent, child_color, color);
+        }
+        if self.omitted > 0 {
+            println!("{}└ … {} more", indent, self.omitted);
+        }
+    }
+
+    /// New and moved entries use the command's `color`, replaced and deleted ones their own.
+    fn mark_color(&self, color: colored::Color) -> Option<colored::Color> {
+        self.mark.map(|mark| match mark {
+            Mark::New | Mark::Moved => color,
+            Mark::Replaced => colored::Color::BrightYellow,
+            Mark::Deleted => colored::Color::BrightRed,
+        })
+    }
+
+    fn line(&self, icon: &str, name: &str, color: Option<colored::Color>) -> String {
+        let line = format!("{} {}", icon, name);
+        match color {
+            Some(color) => line.color(color).to_string(),
+            None => line,
+        }
+    }
+}
+
+/// Applies `operation` to the trees, `deleted` remembers the deleted paths so that
+/// an entry later put in their place is shown as replaced rather than new.
+fn apply(
+    root: &Utf8Path,
+    operation: &Operation,
+    before: &mut Node,
+    after: &mut Node,
+    deleted: &mut BTreeSet<Utf8PathBuf>,
+) {
+    let relative = |path: &Utf8PathBuf| path.strip_prefix(root).unwrap().to_owned();
+    match operation {
+        Operation::CreateDirectory(path) => {
+            let path = relative(path);
+            if after.get_mut(&path).is_none() {
+                after.insert(&path, Node::new(true, Some(Mark::New)));
+            }
+        }
+        Operation::CreateFile(path) => {
+            let path = relative(path);
+            let replaced = after.insert(&path, Node::new(false, Some(Mark::New)));
+            if replaced || deleted.remove(&path) {
+                after.mark(&path, Mark::Replaced);
+            }
+        }
+        Operation::Delete(path) => {
+            let path = relative(path);
+            before.mark(&path, Mark::Deleted);
+            after.remove(&path);
+            deleted.insert(path);
+        }
+        Operation::Rename(from, to) => {
+            let (from, to) = (relative(from), relative(to));
+            before.mark(&from, Mark::Deleted);
+            before.mark(&to, Mark::Deleted);
+            let Some(mut node) = after.remove(&from) else {
+                return;
+            };
+            node.mark = Some(Mark::Moved);
+            let replaced = after.insert(&to, node);
+            if replaced || deleted.remove(&to) {
+                after.mark(&to, Mark::Replaced);
+            }
+        }
+        Operation::Copy(from, to) => {
+            let (from, to) = (relative(from), relative(to));
+            before.mark(&to, Mark::Deleted);
+            let Some(mut node) = after.get_mut(&from).cloned() else {
+                return;
+            };
+            node.mark = Some(Mark::New);
+            let replaced = after.insert(&to, node);
+            if replaced || deleted.remove(&to) {
+                after.mark(&to, Mark::Replaced);
+            }
+        }
+    }
+}
+
+fn root_label(root: &Utf8Path) -> String {
+    let cwd = crate::get_cwd();
+    let cwd = Utf8Path::from_path(&cwd).unwrap();
+    match root.strip_prefix(cwd) {
+        Ok(relative) if relative.as_str().is_empty() => ".".to_owned(),
+        Ok(relative) => relative.to_string(),
+        Err(_) => root.to_string(),
+    }
+}
@@ -0,0 +1,181 @@
+use std::{io::Read, sync::Mutex};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    copy_tree, remove_tree, walk, CopyOptions, Entry, Preserve, StandardOptions, Visitor, WalkError,
+};
+
+/// The SHA-256 checksum of a file, as lowercase hex.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    pub path: Utf8PathBuf,
+    pub sha256: String,
+}
+
+struct Verify<'a> {
+    from: &'a Utf8Path,
+    /// Where the files are compared to, `None` to only compute the checksums
+    to: Option<&'a Utf8Path>,
+    root: &'a Utf8Path,
+    checksums: Mutex<Vec<Checksum>>,
+}
+
+impl Visitor for Verify<'_> {
+    type Directory = ();
+
+    fn enter(&self, _: Option<&()>, _: &Entry) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn visit(&self, _: Option<&()>, entry: &Entry) -> std::io::Result<()> {
+        if !entry.metadata.is_file() {
+            return Ok(());
+        }
+        let sha256 = hash(entry.parent.open_file(entry.name)?)?;
+        let relative = entry.path.strip_prefix(self.from).unwrap();
+        let path = if relative.as_str().is_empty() {
+            self.root.to_owned()
+        } else {
+            self.root.join(relative)
+        };
+        if let Some(to) = self.to {
+            let copy = if relative.as_str().is_empty() {
+                to.to_owned()
+            } else {
+                to.join(relative)
+            };
+            if hash(open_from_disk(&copy)?)? != sha256 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("The checksum of the copy \"{}\" differs", copy),
+                ));
+            }
+        }
+        self.checksums
+            .lock()
+            .unwrap()
+            .push(Checksum { path, sha256 });
+        Ok(())
+    }
+
+    fn leave(&self, _: Option<&()>, _: &Entry, _: &(), _: bool) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Opens the written file at `path` so that it is read back from the disk
+/// rather than from the cache, as far as the system allows.
+fn open_from_disk(path: &Utf8Path) -> std::io::Result<std::fs::File> {
+    let file = std::fs::File::open(path)?;
+    // Only written pages can be dropped from the cache
+    file.sync_all()?;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    rustix::fs::fadvise(&file, 0, 0, rustix::fs::Advice::DontNeed)?;
+    Ok(file)
+}
+
+fn hash(mut file: std::fs::File) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 1 << 20];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => return Ok(format!("{:x}", hasher.finalize())),
+            Ok(length) => hasher.update(&buffer[..length]),
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+fn checksums(
+    from: &Utf8Path,
+    to: Option<&Utf8Path>,
+    root: &Utf8Path,
+) -> Result<Vec<Checksum>, Vec<WalkError>> {
+    let verify = Verify {
+        from,
+        to,
+        root,
+        checksums: Mutex::new(Vec::new()),
+    };
+    let errors = walk(from, &verify);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let mut checksums = verify.checksums.into_inner().unwrap();
+    checksums.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(checksums)
+}
+
+/// Compares the files at `from` to their copies at `to`,
+/// returns the checksums of the copies.
+pub fn verify_tree(from: &Utf8Path, to: &Utf8Path) -> Result<Vec<Checksum>, Vec<WalkError>> {
+    checksums(from, Some(to), to)
+}
+
+/// The checksums of the files at `path`.
+pub fn hash_tree(path: &Utf8Path) -> Result<Vec<Checksum>, Vec<WalkError>> {
+    checksums(path, None, path)
+}
+
+/// Like `move_tree`, but `from` is only removed once all copies match their originals.
+pub fn move_tree_verified(
+    options: &StandardOptions,
+    from: &Utf8Path,
+    to: &Utf8Path,
+) -> Result<Vec<Checksum>, Vec<WalkError>> {
+    let copy_options = CopyOptions {
+        preserve: Preserve::MOVE,
+        ..Default::default()
+    };
+    copy_tree(options, &copy_options, from, to)?;
+    let checksums = verify_tree(from, to)?;
+    remove_tree(options, from)?;
+    Ok(checksums)
+}
+
+/// Writes the checksums of the files at `to` in the format of `sha256sum`
+/// to "to.sha256", with paths relative to the directory containing it.
+pub fn write_manifest(to: &Utf8Path, checksums: &[Checksum]) -> std::io::Result<Utf8PathBuf> {
+    let to = Utf8Path::new(to.as_str().trim_end_matches(std::path::MAIN_SEPARATOR));
+    let parent = to.parent().unwrap_or(Utf8Path::new(""));
+    let manifest: String = checksums
+        .iter()
+        .map(|checksum| {
+            let path = checksum.path.strip_prefix(parent).unwrap_or(&checksum.path);
+            format!("{}  {}\n", checksum.sha256, path)
+        })
+        .collect();
+    let path = Utf8PathBuf::from(format!("{}.sha256", to));
+    std::fs::write(&path, manifest)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(dir.path()).unwrap();
+        for (path, content) in [
+            ("a/x", "x"),
+            ("a/sub/y", "y"),
+            ("b/x", "x"),
+            ("b/sub/y", "z"),
+        ] {
+            std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            std::fs::write(root.join(path), content).unwrap();
+        }
+        let errors = verify_tree(&root.join("a"), &root.join("b")).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, root.join("a/sub/y"));
+        std::fs::write(root.join("b/sub/y"), "y").unwrap();
+        let checksums = verify_tree(&root.join("a"), &root.join("b")).unwrap();
+        assert_eq!(checksums.len(), 2);
+        assert_eq!(checksums[1].path, root.join("b/x"));
+    }
+}
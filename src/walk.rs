@@ -0,0 +1,908 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    time::{Instant, SystemTime},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+
+use crate::{copy_file::copy_contents, Preserve, Progress, Reflink, Sparse, StandardOptions};
+
+// Walking is mostly waiting on the file system, so we use more threads than cores
+const MIN_THREADS: usize = 4;
+const MAX_THREADS: usize = 32;
+
+#[derive(Debug)]
+pub struct WalkError {
+    pub path: Utf8PathBuf,
+    pub error: std::io::Error,
+}
+
+impl std::fmt::Display for WalkError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "Error for \"{}\": {}", self.path, self.error)
+    }
+}
+
+/// An open directory, its entries are accessed relative to it
+/// so that they are not affected by changes to the directory's ancestors.
+pub struct Dir {
+    path: Utf8PathBuf,
+    #[cfg(unix)]
+    fd: std::os::fd::OwnedFd,
+}
+
+#[cfg(unix)]
+impl Dir {
+    pub fn open(path: &Utf8Path) -> std::io::Result<Dir> {
+        use rustix::fs::{Mode, OFlags};
+        let fd = rustix::fs::openat(
+            rustix::fs::CWD,
+            path.as_std_path(),
+            OFlags::RDONLY | OFlags::DIRECTORY | OFlags::CLOEXEC,
+            Mode::empty(),
+        )?;
+        Ok(Dir {
+            path: path.to_owned(),
+            fd,
+        })
+    }
+
+    pub fn open_child(&self, name: &str) -> std::io::Result<Dir> {
+        use rustix::fs::{Mode, OFlags};
+        let fd = rustix::fs::openat(
+            &self.fd,
+            name,
+            OFlags::RDONLY | OFlags::DIRECTORY | OFlags::NOFOLLOW | OFlags::CLOEXEC,
+            Mode::empty(),
+        )?;
+        Ok(Dir {
+            path: self.path.join(name),
+            fd,
+        })
+    }
+
+    fn names(&self) -> std::io::Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in rustix::fs::Dir::read_from(&self.fd)? {
+            let entry = entry?;
+            let name = entry.file_name().to_str().map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Name is not UTF-8")
+            })?;
+            if name != "." && name != ".." {
+                names.push(name.to_owned());
+            }
+        }
+        Ok(names)
+    }
+
+    pub fn remove_file(&self, name: &str) -> std::io::Result<()> {
+        Ok(rustix::fs::unlinkat(
+            &self.fd,
+            name,
+            rustix::fs::AtFlags::empty(),
+        )?)
+    }
+
+    pub fn remove_dir(&self, name: &str) -> std::io::Result<()> {
+        Ok(rustix::fs::unlinkat(
+            &self.fd,
+            name,
+            rustix::fs::AtFlags::REMOVEDIR,
+        )?)
+    }
+
+    pub fn create_dir(&self, name: &str) -> std::io::Result<Dir> {
+        rustix::fs::mkdirat(&self.fd, name, rustix::fs::Mode::from_bits_truncate(0o777))?;
+        self.open_child(name)
+    }
+
+    pub fn open_file(&self, name: &str) -> std::io::Result<std::fs::File> {
+        use rustix::fs::{Mode, OFlags};
+        let fd = rustix::fs::openat(
+            &self.fd,
+            name,
+            OFlags::RDONLY | OFlags::NOFOLLOW | OFlags::CLOEXEC,
+            Mode::empty(),
+        )?;
+        Ok(fd.into())
+    }
+
+    /// The metadata of the entry `name` itself, a symlink is not followed.
+    #[cfg(target_os = "linux")]
+    pub fn symlink_metadata(&self, name: &str) -> std::io::Result<std::fs::Metadata> {
+        use rustix::fs::{Mode, OFlags};
+        // The same as `statat` without following symlinks, but giving std's `Metadata`
+        let fd = rustix::fs::openat(
+            &self.fd,
+            name,
+            OFlags::PATH | OFlags::NOFOLLOW | OFlags::CLOEXEC,
+            Mode::empty(),
+        )?;
+        std::fs::File::from(fd).metadata()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn symlink_metadata(&self, name: &str) -> std::io::Result<std::fs::Metadata> {
+        self.path.join(name).symlink_metadata()
+    }
+
+    pub fn create_file(&self, name: &str) -> std::io::Result<std::fs::File> {
+        use rustix::fs::{Mode, OFlags};
+        let fd = rustix::fs::openat(
+            &self.fd,
+            name,
+            OFlags::WRONLY | OFlags::CREATE | OFlags::EXCL | OFlags::NOFOLLOW | OFlags::CLOEXEC,
+            Mode::from_bits_truncate(0o666),
+        )?;
+        Ok(fd.into())
+    }
+
+    pub fn read_link(&self, name: &str) -> std::io::Result<std::path::PathBuf> {
+        use std::os::unix::ffi::OsStringExt;
+        let target = rustix::fs::readlinkat(&self.fd, name, Vec::new())?;
+        Ok(std::ffi::OsString::from_vec(target.into_bytes()).into())
+    }
+
+    pub fn create_symlink(&self, target: &std::path::Path, name: &str) -> std::io::Result<()> {
+        Ok(rustix::fs::symlinkat(target, &self.fd, name)?)
+    }
+
+    /// Renames the entry `from` to `to` within the directory, replacing `to`.
+    pub fn rename(&self, from: &str, to: &str) -> std::io::Result<()> {
+        Ok(rustix::fs::renameat(&self.fd, from, &self.fd, to)?)
+    }
+
+    /// Links `name` to the entry at `existing`, relative to the directory `base`.
+    pub fn create_hard_link(
+        &self,
+        base: &Dir,
+        existing: &Utf8Path,
+        name: &str,
+    ) -> std::io::Result<()> {
+        Ok(rustix::fs::linkat(
+            &base.fd,
+            existing.as_std_path(),
+            &self.fd,
+            name,
+            rustix::fs::AtFlags::empty(),
+        )?)
+    }
+}
+
+#[cfg(unix)]
+impl std::os::fd::AsFd for Dir {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+#[cfg(not(unix))]
+impl Dir {
+    pub fn open(path: &Utf8Path) -> std::io::Result<Dir> {
+        if !path.is_dir() {
+            return Err(std::io::Error::from(std::io::ErrorKind::NotFound));
+        }
+        Ok(Dir {
+            path: path.to_owned(),
+        })
+    }
+
+    pub fn open_child(&self, name: &str) -> std::io::Result<Dir> {
+        Dir::open(&self.path.join(name))
+    }
+
+    fn names(&self) -> std::io::Result<Vec<String>> {
+        self.path
+            .read_dir_utf8()?
+            .map(|entry| Ok(entry?.file_name().to_owned()))
+            .collect()
+    }
+
+    pub fn remove_file(&self, name: &str) -> std::io::Result<()> {
+        std::fs::remove_file(self.path.join(name))
+    }
+
+    pub fn remove_dir(&self, name: &str) -> std::io::Result<()> {
+        std::fs::remove_dir(self.path.join(name))
+    }
+
+    pub fn create_dir(&self, name: &str) -> std::io::Result<Dir> {
+        std::fs::create_dir(self.path.join(name))?;
+        self.open_child(name)
+    }
+
+    pub fn open_file(&self, name: &str) -> std::io::Result<std::fs::File> {
+        std::fs::File::open(self.path.join(name))
+    }
+
+    pub fn create_file(&self, name: &str) -> std::io::Result<std::fs::File> {
+        std::fs::File::create_new(self.path.join(name))
+    }
+
+    pub fn read_link(&self, name: &str) -> std::io::Result<std::path::PathBuf> {
+        std::fs::read_link(self.path.join(name))
+    }
+
+    pub fn create_symlink(&self, target: &std::path::Path, name: &str) -> std::io::Result<()> {
+        if self.path.as_std_path().join(target).is_dir() {
+            std::os::windows::fs::symlink_dir(target, self.path.join(name))
+        } else {
+            std::os::windows::fs::symlink_file(target, self.path.join(name))
+        }
+    }
+
+    pub fn rename(&self, from: &str, to: &str) -> std::io::Result<()> {
+        std::fs::rename(self.path.join(from), self.path.join(to))
+    }
+
+    pub fn create_hard_link(
+        &self,
+        base: &Dir,
+        existing: &Utf8Path,
+        name: &str,
+    ) -> std::io::Result<()> {
+        std::fs::hard_link(base.path.join(existing), self.path.join(name))
+    }
+
+    pub fn symlink_metadata(&self, name: &str) -> std::io::Result<std::fs::Metadata> {
+        self.path.join(name).symlink_metadata()
+    }
+}
+
+impl Dir {
+    /// Opens the directory containing `path`,
+    /// the paths of its entries are relative if `path` is.
+    pub fn open_parent(path: &Utf8Path) -> std::io::Result<Dir> {
+        match path.parent() {
+            Some(parent) if !parent.as_str().is_empty() => Dir::open(parent),
+            _ => {
+                let mut dir = Dir::open(Utf8Path::new("."))?;
+                dir.path = Utf8PathBuf::new();
+                Ok(dir)
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+}
+
+pub struct Entry<'a> {
+    /// The directory containing the entry
+    pub parent: &'a Dir,
+    pub name: &'a str,
+    pub path: Utf8PathBuf,
+    pub metadata: std::fs::Metadata,
+}
+
+impl Entry<'_> {
+    /// The number of bytes reported as progress, directories and symlinks count as empty.
+    pub fn size(&self) -> u64 {
+        if self.metadata.is_file() {
+            self.metadata.len()
+        } else {
+            0
+        }
+    }
+
+    /// The device and inode of a file with more than one hard link.
+    #[cfg(unix)]
+    fn hard_link_id(&self) -> Option<(u64, u64)> {
+        use std::os::unix::fs::MetadataExt;
+        (self.metadata.is_file() && self.metadata.nlink() > 1)
+            .then(|| (self.metadata.dev(), self.metadata.ino()))
+    }
+
+    #[cfg(not(unix))]
+    fn hard_link_id(&self) -> Option<(u64, u64)> {
+        None
+    }
+}
+
+/// Callbacks of a parallel walk, called from multiple threads at once.
+/// `parent` is the state returned by `enter` for the directory containing the entry,
+/// `None` for the root of the walk.
+pub trait Visitor: Sync {
+    type Directory: Send + Sync;
+
+    /// Called for every entry except the root, skipped entries are neither
+    /// visited nor entered. Nothing is skipped by default.
+    fn skip(&self, _entry: &Entry) -> bool {
+        false
+    }
+
+    /// Called for every directory before any of its contents.
+    fn enter(
+        &self,
+        parent: Option<&Self::Directory>,
+        entry: &Entry,
+    ) -> std::io::Result<Self::Directory>;
+
+    /// Called for every entry which is not a directory.
+    fn visit(&self, parent: Option<&Self::Directory>, entry: &Entry) -> std::io::Result<()>;
+
+    /// Called for every directory after all of its contents,
+    /// `complete` is false if visiting any of them failed.
+    fn leave(
+        &self,
+        parent: Option<&Self::Directory>,
+        entry: &Entry,
+        directory: &Self::Directory,
+        complete: bool,
+    ) -> std::io::Result<()>;
+}
+
+struct Node<S> {
+    parent: Option<Arc<Node<S>>>,
+    parent_dir: Arc<Dir>,
+    dir: Arc<Dir>,
+    name: String,
+    metadata: std::fs::Metadata,
+    state: S,
+    // Contents not yet visited, plus one until the directory is listed
+    remaining: AtomicUsize,
+    complete: AtomicBool,
+}
+
+enum Job<S> {
+    List(Arc<Node<S>>),
+    /// Enters, opens and lists a child directory of the node
+    Enter(Arc<Node<S>>, String, std::fs::Metadata),
+    Visit(Arc<Node<S>>, String, std::fs::Metadata),
+}
+
+struct Walk<'a, V: Visitor> {
+    visitor: &'a V,
+    // The newest jobs are taken first, so that the walk goes depth-first and only the
+    // directories along the paths being walked are open, rather than all listed ones
+    queue: Mutex<(Vec<Job<V::Directory>>, usize)>,
+    available: Condvar,
+    errors: Mutex<Vec<WalkError>>,
+}
+
+/// Walks `root` and all of its descendants in parallel without following symlinks.
+/// Errors are collected per entry, the walk continues with the remaining entries.
+pub fn walk<V: Visitor>(root: &Utf8Path, visitor: &V) -> Vec<WalkError> {
+    let walk = Walk {
+        visitor,
+        queue: Mutex::new((Vec::new(), 0)),
+        available: Condvar::new(),
+        errors: Mutex::new(Vec::new()),
+    };
+    if let Err(error) = walk.start(root) {
+        walk.fail(root.to_owned(), error);
+    }
+    let threads = std::thread::available_parallelism()
+        .map_or(MIN_THREADS, |threads| threads.get())
+        .clamp(MIN_THREADS, MAX_THREADS);
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| walk.work());
+        }
+    });
+    walk.errors.into_inner().unwrap()
+}
+
+impl<V: Visitor> Walk<'_, V> {
+    fn start(&self, root: &Utf8Path) -> std::io::Result<()> {
+        let root = crate::handle_cwd(root);
+        let name = root
+            .file_name()
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+        let parent_dir = Arc::new(Dir::open_parent(&root)?);
+        let entry = Entry {
+            parent: &parent_dir,
+            name,
+            path: root.to_path_buf(),
+            metadata: root.symlink_metadata()?,
+        };
+        if !entry.metadata.is_dir() {
+            return self.visitor.visit(None, &entry);
+        }
+        let state = self.visitor.enter(None, &entry)?;
+        let dir = Arc::new(parent_dir.open_child(name)?);
+        let node = Node {
+            parent: None,
+            parent_dir: parent_dir.clone(),
+            dir,
+            name: name.to_owned(),
+            metadata: entry.metadata,
+            state,
+            remaining: AtomicUsize::new(1),
+            complete: AtomicBool::new(true),
+        };
+        self.push(Job::List(Arc::new(node)));
+        Ok(())
+    }
+
+    fn work(&self) {
+        loop {
+            let job = {
+                let mut queue = self.queue.lock().unwrap();
+                loop {
+                    if let Some(job) = queue.0.pop() {
+                        queue.1 += 1;
+                        break job;
+                    }
+                    if queue.1 == 0 {
+                        self.available.notify_all();
+                        return;
+                    }
+                    queue = self.available.wait(queue).unwrap();
+                }
+            };
+            match job {
+                Job::List(node) => self.list(node),
+                Job::Enter(parent, name, metadata) => self.enter(parent, name, metadata),
+                Job::Visit(parent, name, metadata) => self.visit(parent, name, metadata),
+            }
+            let mut queue = self.queue.lock().unwrap();
+            queue.1 -= 1;
+            if queue.0.is_empty() && queue.1 == 0 {
+                self.available.notify_all();
+            }
+        }
+    }
+
+    fn push(&self, job: Job<V::Directory>) {
+        self.queue.lock().unwrap().0.push(job);
+        self.available.notify_one();
+    }
+
+    fn fail(&self, path: Utf8PathBuf, error: std::io::Error) {
+        self.errors.lock().unwrap().push(WalkError { path, error });
+    }
+
+    fn list(&self, node: Arc<Node<V::Directory>>) {
+        match node.dir.names() {
+            Ok(names) => {
+                node.remaining.fetch_add(names.len(), Ordering::SeqCst);
+                for name in names {
+                    self.list_child(&node, name);
+                }
+            }
+            Err(error) => {
+                node.complete.store(false, Ordering::SeqCst);
+                self.fail(node.dir.path().to_owned(), error);
+            }
+        }
+        self.child_done(node);
+    }
+
+    fn list_child(&self, node: &Arc<Node<V::Directory>>, name: String) {
+        let path = node.dir.path().join(&name);
+        let metadata = match node.dir.symlink_metadata(&name) {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                node.complete.store(false, Ordering::SeqCst);
+                self.fail(path, error);
+                return self.child_done(node.clone());
+            }
+        };
+        let entry = Entry {
+            parent: &node.dir,
+            name: &name,
+            path,
+            metadata,
+        };
+        if self.visitor.skip(&entry) {
+            return self.child_done(node.clone());
+        }
+        if entry.metadata.is_dir() {
+            self.push(Job::Enter(node.clone(), name.clone(), entry.metadata));
+        } else {
+            self.push(Job::Visit(node.clone(), name.clone(), entry.metadata));
+        }
+    }
+
+    fn enter(&self, node: Arc<Node<V::Directory>>, name: String, metadata: std::fs::Metadata) {
+        let entry = Entry {
+            parent: &node.dir,
+            name: &name,
+            path: node.dir.path().join(&name),
+            metadata,
+        };
+        let child = self
+            .visitor
+            .enter(Some(&node.state), &entry)
+            .and_then(|state| Ok((state, node.dir.open_child(&name)?)));
+        match child {
+            Ok((state, dir)) => {
+                let child = Node {
+                    parent: Some(node.clone()),
+                    parent_dir: node.dir.clone(),
+                    dir: Arc::new(dir),
+                    name: name.clone(),
+                    metadata: entry.metadata,
+                    state,
+                    remaining: AtomicUsize::new(1),
+                    complete: AtomicBool::new(true),
+                };
+                self.list(Arc::new(child));
+            }
+            Err(error) => {
+                node.complete.store(false, Ordering::SeqCst);
+                self.fail(entry.path, error);
+                self.child_done(node);
+            }
+        }
+    }
+
+    fn visit(&self, node: Arc<Node<V::Directory>>, name: String, metadata: std::fs::Metadata) {
+        let entry = Entry {
+            parent: &node.dir,
+            name: &name,
+            path: node.dir.path().join(&name),
+            metadata,
+        };
+        if let Err(error) = self.visitor.visit(Some(&node.state), &entry) {
+            node.complete.store(false, Ordering::SeqCst);
+            self.fail(entry.path, error);
+        }
+        self.child_done(node);
+    }
+
+    /// Leaves every directory whose contents were all visited, starting with `node`.
+    fn child_done(&self, node: Arc<Node<V::Directory>>) {
+        let mut node = node;
+        while node.remaining.fetch_sub(1, Ordering::SeqCst) == 1 {
+            let complete = node.complete.load(Ordering::SeqCst);
+            let entry = Entry {
+                parent: &node.parent_dir,
+                name: &node.name,
+                path: node.dir.path().to_owned(),
+                metadata: node.metadata.clone(),
+            };
+            let parent_state = node.parent.as_ref().map(|parent| &parent.state);
+            let left = self
+                .visitor
+                .leave(parent_state, &entry, &node.state, complete);
+            let succeeded = complete && left.is_ok();
+            if let Err(error) = left {
+                self.fail(entry.path, error);
+            }
+            let Some(parent) = node.parent.clone() else {
+                return;
+            };
+            if !succeeded {
+                parent.complete.store(false, Ordering::SeqCst);
+            }
+            node = parent;
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TreeSummary {
+    pub files: u64,
+    pub directories: u64,
+    pub bytes: u64,
+    /// The newest modification time of any entry
+    pub newest: Option<SystemTime>,
+    /// The entry modified at `newest`
+    pub newest_path: Option<Utf8PathBuf>,
+    /// Whether counting stopped at a deadline, the counts are lower bounds then
+    pub truncated: bool,
+}
+
+impl TreeSummary {
+    pub fn entries(&self) -> u64 {
+        self.files + self.directories
+    }
+}
+
+#[derive(Default)]
+struct Counting {
+    files: AtomicU64,
+    directories: AtomicU64,
+    bytes: AtomicU64,
+    newest: Mutex<Option<(SystemTime, Utf8PathBuf)>>,
+    deadline: Option<Instant>,
+    truncated: AtomicBool,
+}
+
+impl Counting {
+    fn record_modified(&self, entry: &Entry) {
+        if let Ok(modified) = entry.metadata.modified() {
+            let mut newest = self.newest.lock().unwrap();
+            if newest.as_ref().is_none_or(|(newest, _)| modified > *newest) {
+                *newest = Some((modified, entry.path.clone()));
+            }
+        }
+    }
+}
+
+impl Visitor for Counting {
+    type Directory = ();
+
+    fn skip(&self, _: &Entry) -> bool {
+        let expired = self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline);
+        if expired {
+            self.truncated.store(true, Ordering::Relaxed);
+        }
+        expired
+    }
+
+    fn enter(&self, _: Option<&()>, entry: &Entry) -> std::io::Result<()> {
+        self.directories.fetch_add(1, Ordering::Relaxed);
+        self.record_modified(entry);
+        Ok(())
+    }
+
+    fn visit(&self, _: Option<&()>, entry: &Entry) -> std::io::Result<()> {
+        self.files.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(entry.size(), Ordering::Relaxed);
+        self.record_modified(entry);
+        Ok(())
+    }
+
+    fn leave(&self, _: Option<&()>, _: &Entry, _: &(), _: bool) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Counts the entries at `path`, entries which cannot be read are skipped.
+pub fn summarize_tree(path: &Utf8Path) -> TreeSummary {
+    summarize(path, None)
+}
+
+/// Like `summarize_tree`, but stops counting at `deadline`, for huge trees.
+pub fn summarize_tree_until(path: &Utf8Path, deadline: Instant) -> TreeSummary {
+    summarize(path, Some(deadline))
+}
+
+fn summarize(path: &Utf8Path, deadline: Option<Instant>) -> TreeSummary {
+    let counting = Counting {
+        deadline,
+        ..Default::default()
+    };
+    walk(path, &counting);
+    let newest = counting.newest.into_inner().unwrap();
+    TreeSummary {
+        files: counting.files.into_inner(),
+        directories: counting.directories.into_inner(),
+        bytes: counting.bytes.into_inner(),
+        newest: newest.as_ref().map(|(modified, _)| *modified),
+        newest_path: newest.map(|(_, path)| path),
+        truncated: counting.truncated.into_inner(),
+    }
+}
+
+struct Removal {
+    progress: Mutex<Progress>,
+}
+
+impl Visitor for Removal {
+    type Directory = ();
+
+    fn enter(&self, _: Option<&()>, _: &Entry) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn visit(&self, _: Option<&()>, entry: &Entry) -> std::io::Result<()> {
+        entry.parent.remove_file(entry.name)?;
+        self.progress.lock().unwrap().advance(entry.size());
+        Ok(())
+    }
+
+    fn leave(&self, _: Option<&()>, entry: &Entry, _: &(), complete: bool) -> std::io::Result<()> {
+        // The failed contents were already reported
+        if complete {
+            entry.parent.remove_dir(entry.name)?;
+            self.progress.lock().unwrap().advance(0);
+        }
+        Ok(())
+    }
+}
+
+/// Removes the file or directory at `path` including all of its contents.
+pub fn remove_tree(options: &StandardOptions, path: &Utf8Path) -> Result<(), Vec<WalkError>> {
+    let removal = Removal {
+        progress: Mutex::new(Progress::deleting(options)),
+    };
+    into_result(walk(path, &removal))
+}
+
+/// How `copy_tree` copies the entries.
+#[derive(Clone)]
+pub struct CopyOptions {
+    /// Files hard-linked to each other are copied once and linked again
+    pub preserve_links: bool,
+    pub preserve: Preserve,
+    pub reflink: Reflink,
+    pub sparse: Sparse,
+}
+
+/// The files copied by `copy_tree`, hard links to earlier copies are not counted.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CopySummary {
+    pub files: u64,
+    /// Files sharing their data with the originals
+    pub reflinked_files: u64,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        CopyOptions {
+            preserve_links: true,
+            preserve: Preserve::MODE,
+            reflink: Reflink::Auto,
+            sparse: Sparse::Auto,
+        }
+    }
+}
+
+struct Copy<'a> {
+    to: &'a Utf8Path,
+    // The directory containing `to`
+    root: Dir,
+    options: &'a CopyOptions,
+    // The copies of files with multiple hard links by the device and inode of the original,
+    // relative to `root`
+    hard_links: Mutex<HashMap<(u64, u64), Utf8PathBuf>>,
+    files: AtomicU64,
+    reflinked_files: AtomicU64,
+    progress: Mutex<Progress>,
+}
+
+impl Copy<'_> {
+    /// Calls `create` with the directory to create the copy of `entry` in, and its name.
+    fn create<T>(
+        &self,
+        parent: Option<&Dir>,
+        entry: &Entry,
+        create: impl FnOnce(&Dir, &str) -> std::io::Result<T>,
+    ) -> std::io::Result<T> {
+        if let Some(parent) = parent {
+            return create(parent, entry.name);
+        }
+        let name = self
+            .to
+            .file_name()
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+        create(&self.root, name)
+    }
+
+    /// Creates the file to copy the contents of `entry` into, or returns `None`
+    /// after linking to an earlier copy of a file hard-linked to `entry`.
+    fn create_file(
+        &self,
+        entry: &Entry,
+        destination: &Dir,
+        name: &str,
+    ) -> std::io::Result<Option<std::fs::File>> {
+        let Some(id) = entry.hard_link_id().filter(|_| self.options.preserve_links) else {
+            return destination.create_file(name).map(Some);
+        };
+        // Held until the first copy exists so that other threads can link to it
+        let mut hard_links = self.hard_links.lock().unwrap();
+        if let Some(copy) = hard_links.get(&id) {
+            destination.create_hard_link(&self.root, copy, name)?;
+            return Ok(None);
+        }
+        let file = destination.create_file(name)?;
+        let directory = destination.path();
+        let directory = directory
+            .strip_prefix(self.root.path())
+            .unwrap_or(directory);
+        hard_links.insert(id, directory.join(name));
+        Ok(Some(file))
+    }
+}
+
+impl Visitor for Copy<'_> {
+    type Directory = Dir;
+
+    fn enter(&self, parent: Option<&Dir>, entry: &Entry) -> std::io::Result<Dir> {
+        self.create(parent, entry, |destination, name| {
+            destination.create_dir(name)
+        })
+    }
+
+    fn visit(&self, parent: Option<&Dir>, entry: &Entry) -> std::io::Result<()> {
+        self.create(parent, entry, |destination, name| {
+            if entry.metadata.is_symlink() {
+                destination.create_symlink(&entry.parent.read_link(entry.name)?, name)?;
+            } else {
+                let Some(to) = self.create_file(entry, destination, name)? else {
+                    return Ok(());
+                };
+                let copied = entry.parent.open_file(entry.name).and_then(|from| {
+                    copy_contents(
+                        &from,
+                        &to,
+                        &entry.metadata,
+                        self.options.reflink,
+                        self.options.sparse,
+                    )
+                });
+                let reflinked = match copied {
+                    Ok(reflinked) => reflinked,
+                    Err(error) => {
+                        // No empty or partial copy is left behind, like when a reflink fails
+                        let _ = destination.remove_file(name);
+                        return Err(error);
+                    }
+                };
+                self.files.fetch_add(1, Ordering::Relaxed);
+                if reflinked {
+                    self.reflinked_files.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            self.options.preserve.apply(entry, destination, name)
+        })?;
+        self.progress.lock().unwrap().advance(entry.size());
+        Ok(())
+    }
+
+    fn leave(&self, parent: Option<&Dir>, entry: &Entry, _: &Dir, _: bool) -> std::io::Result<()> {
+        self.create(parent, entry, |destination, name| {
+            self.options.preserve.apply(entry, destination, name)
+        })?;
+        self.progress.lock().unwrap().advance(0);
+        Ok(())
+    }
+}
+
+/// Copies the file or directory at `from` including all of its contents to `to`.
+pub fn copy_tree(
+    options: &StandardOptions,
+    copy_options: &CopyOptions,
+    from: &Utf8Path,
+    to: &Utf8Path,
+) -> Result<CopySummary, Vec<WalkError>> {
+    let root = Dir::open_parent(to).map_err(|error| {
+        vec![WalkError {
+            path: to.to_owned(),
+            error,
+        }]
+    })?;
+    let copy = Copy {
+        to,
+        root,
+        options: copy_options,
+        hard_links: Mutex::new(HashMap::new()),
+        files: AtomicU64::new(0),
+        reflinked_files: AtomicU64::new(0),
+        progress: Mutex::new(Progress::copying(options, || {
+            let summary = summarize_tree(from);
+            (summary.entries(), summary.bytes)
+        })),
+    };
+    into_result(walk(from, &copy))?;
+    Ok(CopySummary {
+        files: copy.files.into_inner(),
+        reflinked_files: copy.reflinked_files.into_inner(),
+    })
+}
+
+/// Moves `from` to `to` by copying and then removing it, keeping modes and timestamps,
+/// used when a rename is not possible across devices.
+/// `from` is only removed if everything was copied.
+pub fn move_tree(
+    options: &StandardOptions,
+    from: &Utf8Path,
+    to: &Utf8Path,
+) -> Result<(), Vec<WalkError>> {
+    let copy_options = CopyOptions {
+        preserve: Preserve::MOVE,
+        ..Default::default()
+    };
+    copy_tree(options, &copy_options, from, to)?;
+    remove_tree(options, from)
+}
+
+fn into_result(errors: Vec<WalkError>) -> Result<(), Vec<WalkError>> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
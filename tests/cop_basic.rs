@@ -0,0 +1,75 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{cop, env, SUCCESS};
+
+#[test]
+fn copies_file() -> Result<()> {
+    let env = env(&["foo"])?;
+    let res = cop().args(&["foo", "bar"]).env(&env).run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {}",
+            "C".bright_green(),
+            "foo".bright_red(),
+            "bar".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("foo")?, "foo");
+    eq!(env.read("bar")?, "foo");
+    Ok(())
+}
+
+#[test]
+fn copies_directory() -> Result<()> {
+    let env = env(&["foo/a", "foo/b/c", "foo/b/d/e"])?;
+    let res = cop().args(&["foo", "bar"]).env(&env).run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("foo/b/d/e")?, "foo/b/d/e");
+    eq!(env.read("bar/a")?, "foo/a");
+    eq!(env.read("bar/b/c")?, "foo/b/c");
+    eq!(env.read("bar/b/d/e")?, "foo/b/d/e");
+    Ok(())
+}
+
+#[test]
+fn copies_into_directory() -> Result<()> {
+    let env = env(&["foo", "bar/baz"])?;
+    let res = cop().args(&["foo", "bar", "dest/"]).env(&env).run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("dest/foo")?, "foo");
+    eq!(env.read("dest/bar/baz")?, "bar/baz");
+    ensure!(env.exists("foo"));
+    Ok(())
+}
+
+#[test]
+fn replaces_directory() -> Result<()> {
+    let env = env(&["foo/a", "bar/b"])?;
+    let res = cop().args(&["foo", "bar"]).answer("y").env(&env).run()?;
+    eq!(
+        res.prompt,
+        "bar/ (1 file, 5 B, newest modified just now)\n  b\nDirectory \"bar\" already exists, replace it? [y/N]"
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("bar/a")?, "foo/a");
+    ensure!(!env.exists("bar/b"));
+    Ok(())
+}
+
+#[test]
+fn cannot_copy_into_itself() -> Result<()> {
+    let env = env(&["foo/a"])?;
+    let res = cop().args(&["foo", "foo/bar"]).env(&env).run()?;
+    eq!(
+        res.error,
+        "Error: Cannot copy \"foo\" into itself at \"foo/bar\""
+    );
+    ensure!(res.code != SUCCESS);
+    ensure!(!env.exists("foo/bar"));
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use anyhow::{ensure, Ok, Result};
+
+mod utils;
+
+use crate::utils::{cop, env, SUCCESS};
+
+#[test]
+fn preserves_hard_links() -> Result<()> {
+    let env = env(&["foo/a", "foo/sub/x", "foo/other"])?;
+    env.write("foo/a", "shared")?;
+    env.hard_link("foo/a", "foo/b")?;
+    env.hard_link("foo/a", "foo/sub/c")?;
+    let res = cop().args(&["foo", "bar"]).env(&env).run()?;
+    ensure!(res.code == SUCCESS);
+    ensure!(env.is_same_file("bar/a", "bar/b")?);
+    ensure!(env.is_same_file("bar/a", "bar/sub/c")?);
+    ensure!(!env.is_same_file("bar/a", "foo/a")?);
+    ensure!(!env.is_same_file("bar/a", "bar/other")?);
+    eq!(env.read("bar/sub/c")?, "shared");
+    Ok(())
+}
+
+#[test]
+fn no_preserve_links_copies_independently() -> Result<()> {
+    let env = env(&["foo/a"])?;
+    env.hard_link("foo/a", "foo/b")?;
+    let res = cop()
+        .args(&["foo", "bar", "--no-preserve-links"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.is_same_file("bar/a", "bar/b")?);
+    Ok(())
+}
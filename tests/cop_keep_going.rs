@@ -0,0 +1,52 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{cop, env, SUCCESS};
+
+const PARTIAL_FAILURE: i32 = 2;
+
+#[test]
+fn copies_the_other_sources() -> Result<()> {
+    let env = env(&["a", "b", "dest/y"])?;
+    let res = cop()
+        .args(&["a", "missing", "b", "dest/", "--keep-going"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {}{}\n{} {} -> {}{}",
+            "C".bright_green(),
+            "a".bright_red(),
+            "dest/",
+            "a".bright_green(),
+            "C".bright_green(),
+            "b".bright_red(),
+            "dest/",
+            "b".bright_green()
+        )
+    );
+    ensure!(res
+        .error
+        .starts_with("Error for \"missing\": No such file or directory"));
+    ensure!(res.error.ends_with("2 done, 1 failed"));
+    eq!(res.code, PARTIAL_FAILURE);
+    eq!(env.read("dest/a")?, "a");
+    eq!(env.read("dest/b")?, "b");
+    eq!(env.read("a")?, "a");
+    Ok(())
+}
+
+#[test]
+fn fails_when_nothing_is_copied() -> Result<()> {
+    let env = env(&["dest/y"])?;
+    let res = cop()
+        .args(&["missing", "dest/", "--keep-going"])
+        .env(&env)
+        .run()?;
+    ensure!(res.error.ends_with("0 done, 1 failed"));
+    ensure!(res.code != SUCCESS && res.code != PARTIAL_FAILURE);
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{cop, env, SUCCESS};
+
+#[test]
+fn merges_into_existing_directory() -> Result<()> {
+    let env = env(&["a/x", "a/sub/y", "dest/b/x", "dest/b/sub/z"])?;
+    let res = cop()
+        .args(&["a", "dest/b", "--merge", "--force"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {}\n{} {}",
+            "N".bright_green(),
+            "dest/b/sub/y".bright_green(),
+            "E".bright_green(),
+            "dest/b/x".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("dest/b/x")?, "a/x");
+    eq!(env.read("dest/b/sub/y")?, "a/sub/y");
+    eq!(env.read("dest/b/sub/z")?, "dest/b/sub/z");
+    eq!(env.read("a/x")?, "a/x");
+    Ok(())
+}
+
+#[test]
+fn merges_into_directory() -> Result<()> {
+    let env = env(&["a/x", "dest/a/y"])?;
+    let res = cop().args(&["a", "dest/", "--merge"]).env(&env).run()?;
+    eq!(
+        res.output,
+        format!("{} {}", "N".bright_green(), "dest/a/x".bright_green())
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("dest/a/x")?, "a/x");
+    eq!(env.read("dest/a/y")?, "dest/a/y");
+    Ok(())
+}
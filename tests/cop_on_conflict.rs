@@ -0,0 +1,105 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{cop, env, SUCCESS};
+
+#[test]
+fn overwrite_does_not_ask() -> Result<()> {
+    let env = env(&["foo", "bar"])?;
+    let res = cop()
+        .args(&["foo", "bar", "--on-conflict=overwrite"])
+        .env(&env)
+        .run()?;
+    eq!(res.prompt, "");
+    eq!(
+        res.output,
+        format!(
+            "{}\n{} {} -> {} (on conflict: overwrite)",
+            "D bar".bright_red(),
+            "C".bright_green(),
+            "foo".bright_red(),
+            "bar".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("bar")?, "foo");
+    Ok(())
+}
+
+#[test]
+fn larger_keeps_larger_existing() -> Result<()> {
+    let env = env(&["a/x", "a/y", "b/x", "b/y"])?;
+    env.write("a/x", "longer than the existing file")?;
+    env.write("a/y", "")?;
+    let res = cop()
+        .args(&["a/x", "a/y", "b/", "--on-conflict=larger"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{}\n{} {} -> b/{} (on conflict: larger)\nSkipped a/y -> b/y (on conflict: larger)",
+            "D b/x".bright_red(),
+            "C".bright_green(),
+            "a/x".bright_red(),
+            "x".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("b/x")?, "longer than the existing file");
+    eq!(env.read("b/y")?, "b/y");
+    Ok(())
+}
+
+#[test]
+fn rename_directory_keeps_extension_in_name() -> Result<()> {
+    let env = env(&["a.d/x", "b/a.d/x"])?;
+    let res = cop()
+        .args(&["a.d", "b/", "--on-conflict=rename"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("b/a.d (1)/x")?, "a.d/x");
+    eq!(env.read("b/a.d/x")?, "b/a.d/x");
+    Ok(())
+}
+
+#[test]
+fn tree_tells_replaced_from_new() -> Result<()> {
+    let env = env(&["a/x", "a/y", "b/x"])?;
+    let res = cop()
+        .args(&[
+            "a/x",
+            "a/y",
+            "b/",
+            "--on-conflict=overwrite",
+            "--dry-run",
+            "--tree",
+        ])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{}\n{} {} -> b/{} (on conflict: overwrite)\n{} {} -> b/{}\n\
+             Before:\n📂 .\n├ 📂 a\n│ ├ 📜 x\n│ └ 📜 y\n└ 📂 b\n  └ {}\n\
+             After:\n📂 .\n├ 📂 a\n│ ├ 📜 x\n│ └ 📜 y\n└ 📂 b\n  ├ {}\n  └ {}",
+            "D b/x".bright_red(),
+            "C".bright_green(),
+            "a/x".bright_red(),
+            "x".bright_green(),
+            "C".bright_green(),
+            "a/y".bright_red(),
+            "y".bright_green(),
+            "📜 x".bright_red(),
+            "📜 x".bright_yellow(),
+            "📜 y".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("b/x")?, "b/x");
+    ensure!(!env.exists("b/y"));
+    Ok(())
+}
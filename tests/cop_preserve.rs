@@ -0,0 +1,82 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::{ensure, Ok, Result};
+
+mod utils;
+
+use crate::utils::{cop, env, SUCCESS};
+
+const LONG_AGO: Duration = Duration::from_secs(1_000_000_000);
+
+#[test]
+fn keeps_modes_by_default() -> Result<()> {
+    let env = env(&["foo/sub/file"])?;
+    env.set_mode("foo/sub/file", 0o640)?;
+    env.set_mode("foo/sub", 0o550)?;
+    let res = cop().args(&["foo", "bar"]).env(&env).run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.mode("bar/sub/file")?, 0o640);
+    eq!(env.mode("bar/sub")?, 0o550);
+    Ok(())
+}
+
+#[test]
+fn does_not_keep_timestamps_by_default() -> Result<()> {
+    let env = env(&["foo"])?;
+    env.set_modified("foo", SystemTime::UNIX_EPOCH + LONG_AGO)?;
+    let res = cop().args(&["foo", "bar"]).env(&env).run()?;
+    ensure!(res.code == SUCCESS);
+    ensure!(env.modified("bar")? != SystemTime::UNIX_EPOCH + LONG_AGO);
+    Ok(())
+}
+
+#[test]
+fn keeps_timestamps_of_files_and_directories() -> Result<()> {
+    let env = env(&["foo/sub/file"])?;
+    let time = SystemTime::UNIX_EPOCH + LONG_AGO;
+    env.set_modified("foo/sub/file", time)?;
+    env.set_modified("foo/sub", time)?;
+    env.set_modified("foo", time)?;
+    let res = cop()
+        .args(&["foo", "bar", "--preserve=timestamps"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.modified("bar/sub/file")?, time);
+    eq!(env.modified("bar/sub")?, time);
+    eq!(env.modified("bar")?, time);
+    Ok(())
+}
+
+#[test]
+fn keeps_extended_attributes() -> Result<()> {
+    let env = env(&["foo/file"])?;
+    env.set_xattr("foo/file", "user.origin", "upstream")?;
+    env.set_xattr("foo", "user.origin", "root")?;
+    let res = cop()
+        .args(&["foo", "bar", "--preserve=xattr"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.xattr("bar/file", "user.origin")?, "upstream");
+    eq!(env.xattr("bar", "user.origin")?, "root");
+    Ok(())
+}
+
+#[test]
+fn all_keeps_everything() -> Result<()> {
+    let env = env(&["foo"])?;
+    let time = SystemTime::UNIX_EPOCH + LONG_AGO;
+    env.set_xattr("foo", "user.origin", "upstream")?;
+    env.set_mode("foo", 0o600)?;
+    env.set_modified("foo", time)?;
+    let res = cop()
+        .args(&["foo", "bar", "--preserve=all"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.xattr("bar", "user.origin")?, "upstream");
+    eq!(env.mode("bar")?, 0o600);
+    eq!(env.modified("bar")?, time);
+    Ok(())
+}
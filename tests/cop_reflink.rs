@@ -0,0 +1,56 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{cop, env, SUCCESS};
+
+#[test]
+fn never_copies_the_data() -> Result<()> {
+    let env = env(&["foo"])?;
+    let res = cop()
+        .args(&["foo", "bar", "--reflink=never"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {}",
+            "C".bright_green(),
+            "foo".bright_red(),
+            "bar".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("bar")?, "foo");
+    Ok(())
+}
+
+#[test]
+fn auto_copies_large_files() -> Result<()> {
+    let env = env(&["foo/large"])?;
+    let content = "0123456789abcdef".repeat(200_000);
+    env.write("foo/large", &content)?;
+    let res = cop().args(&["foo", "bar"]).env(&env).run()?;
+    ensure!(res.code == SUCCESS);
+    ensure!(env.read("bar/large")? == content);
+    Ok(())
+}
+
+#[test]
+fn always_reflinks_or_fails() -> Result<()> {
+    let env = env(&["foo"])?;
+    let res = cop()
+        .args(&["foo", "bar", "--reflink=always"])
+        .env(&env)
+        .run()?;
+    // Whether reflinks are supported depends on the file system of the temporary directory
+    if res.code == SUCCESS {
+        ensure!(res.output.ends_with(" (reflinked)"));
+        eq!(env.read("bar")?, "foo");
+    } else {
+        ensure!(res.error.contains("Cannot create a reflink"));
+        ensure!(!env.exists("bar"));
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use anyhow::{ensure, Ok, Result};
+
+mod utils;
+
+use crate::utils::{cop, env, SUCCESS};
+
+const LENGTH: u64 = 64 << 20;
+const OFFSET: u64 = 32 << 20;
+
+#[test]
+fn keeps_holes() -> Result<()> {
+    let env = env(&["image"])?;
+    env.write_sparse("image", LENGTH, OFFSET, "data")?;
+    let res = cop().args(&["image", "copy"]).env(&env).run()?;
+    ensure!(res.code == SUCCESS);
+    ensure!(env.allocated("copy")? < 1 << 20);
+    let content = env.read_bytes("copy")?;
+    eq!(content.len() as u64, LENGTH);
+    eq!(&content[OFFSET as usize..OFFSET as usize + 4], b"data");
+    Ok(())
+}
+
+#[test]
+fn never_fills_holes() -> Result<()> {
+    let env = env(&["image"])?;
+    env.write_sparse("image", LENGTH, OFFSET, "data")?;
+    let res = cop()
+        .args(&["image", "copy", "--sparse=never"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    ensure!(env.allocated("copy")? >= LENGTH);
+    Ok(())
+}
+
+#[test]
+fn always_turns_zeros_into_holes() -> Result<()> {
+    let env = env(&["zeros"])?;
+    let mut content = vec![0; LENGTH as usize];
+    content[OFFSET as usize..OFFSET as usize + 4].copy_from_slice(b"data");
+    env.write_bytes("zeros", &content)?;
+    let res = cop()
+        .args(&["zeros", "copy", "--sparse=always"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    ensure!(env.allocated("copy")? < 1 << 20);
+    ensure!(env.read_bytes("copy")? == content);
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{cop, env, SUCCESS};
+
+#[test]
+fn notes_verification() -> Result<()> {
+    let env = env(&["foo"])?;
+    let res = cop().args(&["foo", "bar", "--verify"]).env(&env).run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {} (verified)",
+            "C".bright_green(),
+            "foo".bright_red(),
+            "bar".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists("bar.sha256"));
+    Ok(())
+}
+
+#[test]
+fn writes_manifest_next_to_destination() -> Result<()> {
+    let env = env(&["a/x", "a/y", "dest/other"])?;
+    let res = cop()
+        .args(&["a", "dest/", "--verify", "--manifest"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> dest/{} (verified, checksums in dest/a.sha256)",
+            "C".bright_green(),
+            "a".bright_red(),
+            "a".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(
+        env.read("dest/a.sha256")?,
+        "1653a06856ec14bc20b4e1dcc951d601eda9f17d7af17cfd4a433cfe93a29b04  a/x\n\
+         cd06f2411168826a49e9114d3380b5108267f9ea25c68a77a7c62659494f097b  a/y\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn manifest_requires_verify() -> Result<()> {
+    let env = env(&["foo"])?;
+    let res = cop().args(&["foo", "bar", "--manifest"]).env(&env).run()?;
+    ensure!(res.code != SUCCESS);
+    ensure!(!env.exists("bar"));
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use anyhow::{ensure, Ok, Result};
+
+mod utils;
+
+use crate::utils::{cop, env, SUCCESS};
+
+#[test]
+fn copies_more_directories_than_can_be_open() -> Result<()> {
+    let files: Vec<_> = (0..500).map(|i| format!("root/d{}/sub/f", i)).collect();
+    let env = env(&files.iter().map(String::as_str).collect::<Vec<_>>())?;
+    let res = cop()
+        .args(&["root", "copy"])
+        .open_files_limit(64)
+        .env(&env)
+        .run()?;
+    eq!(res.error, "");
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("copy/d499/sub/f")?, "root/d499/sub/f");
+    Ok(())
+}
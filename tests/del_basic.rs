@@ -8,8 +8,11 @@ use crate::utils::{del, env, SUCCESS};
 #[test]
 fn removes_file() -> Result<()> {
     let env = env(&["foo"])?;
-    let res = del().args(&["foo"]).answer("").env(&env).run()?;
-    eq!(res.prompt, "Delete file \"foo\"? [Y/n]");
+    let res = del().args(&["foo"]).answer("y").env(&env).run()?;
+    eq!(
+        res.prompt,
+        "Delete file \"foo\" (3 B, modified just now)? [y/N]"
+    );
     eq!(res.output, "D foo".bright_red().to_string());
     ensure!(res.code == SUCCESS);
     ensure!(!env.exists("foo"));
@@ -19,8 +22,11 @@ fn removes_file() -> Result<()> {
 #[test]
 fn removes_directory() -> Result<()> {
     let env = env(&["foo/lorem"])?;
-    let res = del().args(&["foo"]).answer("").env(&env).run()?;
-    eq!(res.prompt, "Delete directory \"foo\"? [Y/n]");
+    let res = del().args(&["foo"]).answer("y").env(&env).run()?;
+    eq!(
+        res.prompt,
+        "foo/ (1 file, 9 B, newest modified just now)\n  lorem\nDelete directory \"foo\"? [y/N]"
+    );
     eq!(res.output, "D foo/".bright_red().to_string());
     ensure!(res.code == SUCCESS);
     ensure!(!env.exists("foo/lorem"));
@@ -0,0 +1,57 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{del, env, SUCCESS};
+
+#[test]
+fn warns_about_incoming_symlinks() -> Result<()> {
+    let env = env(&["data/file"])?;
+    env.symlink("../data/file", "app/config")?;
+    env.symlink("file", "data/inside")?;
+    let res = del()
+        .args(&["data", "--check-links"])
+        .answer("n")
+        .env(&env)
+        .run()?;
+    eq!(
+        res.prompt,
+        format!(
+            "The following symlinks will no longer resolve...\napp/config -> {}\ndata/ (2 files, 9 B, newest modified just now)\n  file\n  inside\nDelete directory \"data\"? [y/N]",
+            "../data/file".bright_red()
+        )
+    );
+    ensure!(res.code != SUCCESS);
+    ensure!(env.exists("data/file"));
+    Ok(())
+}
+
+#[test]
+fn force_prints_warnings() -> Result<()> {
+    let env = env(&["data/file"])?;
+    env.symlink("../data/file", "app/config")?;
+    let res = del()
+        .args(&["data", "--check-links", "--force"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.error,
+        "Warning: The symlink \"app/config\" -> \"../data/file\" will no longer resolve."
+    );
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists("data"));
+    Ok(())
+}
+
+#[test]
+fn without_check_links_does_not_warn() -> Result<()> {
+    let env = env(&["data/file"])?;
+    env.symlink("../data/file", "app/config")?;
+    let res = del().args(&["data"]).answer("n").env(&env).run()?;
+    eq!(
+        res.prompt,
+        "data/ (1 file, 9 B, newest modified just now)\n  file\nDelete directory \"data\"? [y/N]"
+    );
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use anyhow::{ensure, Ok, Result};
+
+mod utils;
+
+use crate::utils::{del, env, SUCCESS};
+
+#[test]
+fn lists_processes_in_the_prompt() -> Result<()> {
+    let env = env(&["busy/file"])?;
+    let mut process = std::process::Command::new("sleep")
+        .arg("30")
+        .current_dir(env.path("busy"))
+        .spawn()?;
+    let res = del()
+        .args(&["busy", "--check-open"])
+        .answer("n")
+        .env(&env)
+        .run();
+    process.kill()?;
+    process.wait()?;
+    let res = res?;
+    ensure!(res
+        .prompt
+        .starts_with("The following processes hold paths open...\n"));
+    let line = format!("{} sleep: ", process.id());
+    ensure!(res
+        .prompt
+        .lines()
+        .any(|prompt_line| prompt_line.starts_with(&line)
+            && prompt_line.ends_with("(working directory)")));
+    ensure!(res.code != SUCCESS);
+    ensure!(env.exists("busy/file"));
+    Ok(())
+}
+
+#[test]
+fn without_check_open_does_not_list() -> Result<()> {
+    let env = env(&["busy/file"])?;
+    let mut process = std::process::Command::new("sleep")
+        .arg("30")
+        .current_dir(env.path("busy"))
+        .spawn()?;
+    let res = del().args(&["busy"]).answer("n").env(&env).run();
+    process.kill()?;
+    process.wait()?;
+    ensure!(!res?.prompt.contains("processes"));
+    Ok(())
+}
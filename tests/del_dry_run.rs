@@ -9,7 +9,7 @@ use crate::utils::{del, env};
 #[rstest]
 fn dry_run_doesnt_perform_changes(#[values("-n", "--dry-run")] option: &str) -> Result<()> {
     let env = env(&["foo"])?;
-    let res = del().args(&["foo", option]).answer("").env(&env).run()?;
+    let res = del().args(&["foo", option]).answer("y").env(&env).run()?;
     eq!(res.output, format!("{}", "D foo".bright_red()));
     ensure!(env.exists("foo"));
     Ok(())
@@ -0,0 +1,43 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{del, env, SUCCESS};
+
+#[test]
+fn stages_removals() -> Result<()> {
+    let env = env(&["a", "dir/b", "dir/c"])?;
+    env.git_init()?;
+    let res = del()
+        .args(&["a", "dir", "--git", "--force"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} (staged)\n{} (staged)",
+            "D a".bright_red(),
+            "D dir/".bright_red()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(
+        env.git(&["status", "--porcelain"])?,
+        "D  a\nD  dir/b\nD  dir/c\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn dry_run_keeps_index() -> Result<()> {
+    let env = env(&["a"])?;
+    env.git_init()?;
+    let res = del()
+        .args(&["a", "--git", "--force", "--dry-run"])
+        .env(&env)
+        .run()?;
+    eq!(res.output, format!("{}", "D a".bright_red()));
+    eq!(env.git(&["status", "--porcelain"])?, "");
+    Ok(())
+}
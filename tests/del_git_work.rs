@@ -0,0 +1,115 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{del, env, Environment, SUCCESS};
+
+/// A repository at "repo" with one commit which is also on a remote.
+fn pushed_repository(env: &Environment) -> Result<()> {
+    env.git(&["-C", "repo", "init", "--quiet"])?;
+    env.git(&["-C", "repo", "config", "user.name", "Test"])?;
+    env.git(&["-C", "repo", "config", "user.email", "test@example.com"])?;
+    env.git(&["-C", "repo", "add", "--all"])?;
+    env.git(&["-C", "repo", "commit", "--quiet", "--message", "Initial"])?;
+    env.git(&[
+        "-C",
+        "repo",
+        "update-ref",
+        "refs/remotes/origin/main",
+        "HEAD",
+    ])?;
+    Ok(())
+}
+
+#[test]
+fn requires_typing_delete_for_unsaved_work() -> Result<()> {
+    let env = env(&["repo/a", "repo/b"])?;
+    pushed_repository(&env)?;
+    env.write("repo/a", "changed")?;
+    env.write("repo/c", "new")?;
+    let res = del().args(&["repo"]).answer("delete").env(&env).run()?;
+    eq!(
+        res.prompt,
+        format!(
+            "The following git repositories contain work which exists nowhere else...\nrepo/: {}\nrepo/ (16 files, 18 directories, 1.1 KB, newest modified just now)\n  .git/\n  a\n  b\n  c\nDelete directory \"repo\"? [type \"delete\"]",
+            "1 modified file, 1 untracked file".bright_red()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists("repo"));
+    Ok(())
+}
+
+#[test]
+fn yes_keeps_unsaved_work() -> Result<()> {
+    let env = env(&["repo/a"])?;
+    pushed_repository(&env)?;
+    env.git(&[
+        "-C",
+        "repo",
+        "commit",
+        "--quiet",
+        "--allow-empty",
+        "--message",
+        "Local",
+    ])?;
+    let res = del().args(&["repo"]).answer("y").env(&env).run()?;
+    eq!(
+        res.prompt,
+        format!(
+            "The following git repositories contain work which exists nowhere else...\nrepo/: {}\nrepo/ (14 files, 18 directories, 1.4 KB, newest modified just now)\n  .git/\n  a\nDelete directory \"repo\"? [type \"delete\"]",
+            "1 unpushed commit".bright_red()
+        )
+    );
+    ensure!(res.code != SUCCESS);
+    ensure!(env.exists("repo/a"));
+    Ok(())
+}
+
+#[test]
+fn finds_nested_repositories() -> Result<()> {
+    let env = env(&["dir/repo/a", "other"])?;
+    env.git(&["-C", "dir/repo", "init", "--quiet"])?;
+    let res = del()
+        .args(&["dir", "other"])
+        .answer("delete")
+        .env(&env)
+        .run()?;
+    eq!(
+        res.prompt,
+        format!(
+            "The following git repositories contain work which exists nowhere else...\ndir/repo/: {}\nFor the following...\ndir/ (3 files, 8 directories, 125 B, newest modified just now)\n  repo/\nother (5 B, modified just now)\n...delete all? [type \"delete\"]",
+            "1 untracked file".bright_red()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists("dir"));
+    Ok(())
+}
+
+#[test]
+fn saved_work_asks_as_usual() -> Result<()> {
+    let env = env(&["repo/a"])?;
+    pushed_repository(&env)?;
+    let res = del().args(&["repo"]).answer("y").env(&env).run()?;
+    eq!(
+        res.prompt,
+        "repo/ (13 files, 17 directories, 993 B, newest modified just now)\n  .git/\n  a\nDelete directory \"repo\"? [y/N]"
+    );
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists("repo"));
+    Ok(())
+}
+
+#[test]
+fn force_does_not_look_for_unsaved_work() -> Result<()> {
+    let env = env(&["repo/a"])?;
+    pushed_repository(&env)?;
+    env.write("repo/b", "new")?;
+    let res = del().args(&["repo", "--force"]).env(&env).run()?;
+    eq!(res.error, "");
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists("repo"));
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{del, env, SUCCESS};
+
+const PARTIAL_FAILURE: i32 = 2;
+
+#[test]
+fn summarizes_deletions() -> Result<()> {
+    let env = env(&["a", "dir/b"])?;
+    let res = del()
+        .args(&["a", "missing", "dir", "--keep-going", "--force"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{}\n{}\n2 done, 0 failed",
+            "D a".bright_red(),
+            "D dir/".bright_red()
+        )
+    );
+    eq!(res.error, "");
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists("a"));
+    ensure!(!env.exists("dir"));
+    Ok(())
+}
+
+#[test]
+fn deletes_the_other_paths_when_one_fails() -> Result<()> {
+    let env = env(&["a", "dir/b", "other/stuck"])?;
+    // Root can only be kept from deleting by immutable entries
+    if !env.chattr("+i", "dir") || !env.chattr("+i", "other/stuck") {
+        eprintln!("Skipped: \"chattr +i\" is not available");
+        return Ok(());
+    }
+    let res = del()
+        .args(&["a", "dir", "other", "--keep-going", "--force"])
+        .env(&env)
+        .run();
+    env.chattr("-i", "dir");
+    env.chattr("-i", "other/stuck");
+    let res = res?;
+    eq!(res.output, format!("{}", "D a".bright_red()));
+    // The directory is checked before deleting, the entries inside only fail when deleted
+    eq!(
+        res.error,
+        "Error for \"dir\": Immutable (chattr +i)\n\
+         Error for \"other/stuck\": Operation not permitted (os error 1)\n\
+         1 done, 2 failed"
+    );
+    eq!(res.code, PARTIAL_FAILURE);
+    ensure!(!env.exists("a"));
+    ensure!(env.exists("dir/b"));
+    Ok(())
+}
@@ -8,10 +8,10 @@ use crate::utils::{del, env, SUCCESS};
 #[test]
 fn removes_files() -> Result<()> {
     let env = env(&["foo", "bar"])?;
-    let res = del().args(&["foo", "bar"]).answer("").env(&env).run()?;
+    let res = del().args(&["foo", "bar"]).answer("y").env(&env).run()?;
     eq!(
         res.prompt,
-        "For the following...\nfoo\nbar\n...delete all? [Y/n]"
+        "For the following...\nfoo (3 B, modified just now)\nbar (3 B, modified just now)\n...delete all? [y/N]"
     );
     println!("{:?}", res.output);
     eq!(
@@ -27,10 +27,10 @@ fn removes_files() -> Result<()> {
 #[test]
 fn removes_directories() -> Result<()> {
     let env = env(&["foo/lorem", "bar/ipsum"])?;
-    let res = del().args(&["foo", "bar"]).answer("").env(&env).run()?;
+    let res = del().args(&["foo", "bar"]).answer("y").env(&env).run()?;
     eq!(
         res.prompt,
-        "For the following...\nfoo/\nbar/\n...delete all? [Y/n]"
+        "For the following...\nfoo/ (1 file, 9 B, newest modified just now)\n  lorem\nbar/ (1 file, 9 B, newest modified just now)\n  ipsum\n...delete all? [y/N]"
     );
     eq!(
         res.output,
@@ -43,3 +43,16 @@ fn removes_directories() -> Result<()> {
     ensure!(!env.exists("bar"));
     Ok(())
 }
+
+#[test]
+fn removes_large_directory() -> Result<()> {
+    let files: Vec<String> = (0..20)
+        .flat_map(|i| (0..20).map(move |j| format!("foo/{}/{}/file", i, j)))
+        .collect();
+    let env = env(&files.iter().map(String::as_str).collect::<Vec<_>>())?;
+    let res = del().args(&["foo", "--force"]).env(&env).run()?;
+    eq!(res.error, "");
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists("foo"));
+    Ok(())
+}
@@ -28,10 +28,10 @@ fn nonexistent_paths_fail() -> Result<()> {
 #[test]
 fn mix_of_existing_and_not_succeeds() -> Result<()> {
     let env = env(&["foo"])?;
-    let res = del().args(&["foo", "bar"]).answer("").env(&env).run()?;
+    let res = del().args(&["foo", "bar"]).answer("y").env(&env).run()?;
     eq!(
         res.prompt,
-        "For the following...\nfoo\n...delete all existing? [Y/n]"
+        "For the following...\nfoo (3 B, modified just now)\n...delete all existing? [y/N]"
     );
     ensure!(res.error.starts_with("\"bar\" error:"));
     eq!(res.output, format!("{}", "D foo".bright_red()));
@@ -0,0 +1,30 @@
+use anyhow::{ensure, Ok, Result};
+
+mod utils;
+
+use crate::utils::{del, env, SUCCESS};
+
+#[test]
+fn without_terminal_does_nothing() -> Result<()> {
+    let env = env(&["foo"])?;
+    let res = del().args(&["foo"]).without_terminal().env(&env).run()?;
+    ensure!(res
+        .error
+        .starts_with("Error: Cannot ask for confirmation, no terminal is available"));
+    ensure!(res.code != SUCCESS);
+    ensure!(env.exists("foo"));
+    Ok(())
+}
+
+#[test]
+fn without_terminal_force_deletes() -> Result<()> {
+    let env = env(&["foo"])?;
+    let res = del()
+        .args(&["foo", "--force"])
+        .without_terminal()
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists("foo"));
+    Ok(())
+}
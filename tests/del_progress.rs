@@ -0,0 +1,22 @@
+use anyhow::{ensure, Ok, Result};
+use rstest::rstest;
+
+mod utils;
+
+use crate::utils::{del, env, SUCCESS};
+
+// The tests pipe stderr, so progress must not be shown with or without --silent
+#[rstest]
+fn prints_no_progress_without_terminal(
+    #[values(None, Some("--silent"))] option: Option<&str>,
+) -> Result<()> {
+    let files: Vec<_> = (0..100).map(|i| format!("foo/{}", i)).collect();
+    let env = env(&files.iter().map(String::as_str).collect::<Vec<_>>())?;
+    let mut args = vec!["foo"];
+    args.extend(option);
+    let res = del().args(&args).answer("y").env(&env).run()?;
+    eq!(res.error, "");
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists("foo"));
+    Ok(())
+}
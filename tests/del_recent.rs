@@ -0,0 +1,89 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{del, env, SUCCESS};
+
+const HOUR: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+#[test]
+fn requires_typing_delete_for_recent_file() -> Result<()> {
+    let env = env(&["a"])?;
+    let res = del()
+        .args(&["a", "--recent=10"])
+        .answer("delete")
+        .env(&env)
+        .run()?;
+    eq!(
+        res.prompt,
+        format!(
+            "Delete file \"a\" (1 B, {})? [type \"delete\"]",
+            "modified just now".bright_yellow()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists("a"));
+    Ok(())
+}
+
+#[test]
+fn names_newest_recent_descendant() -> Result<()> {
+    let env = env(&["dir/old", "dir/sub/new"])?;
+    let earlier = std::time::SystemTime::now() - 2 * HOUR;
+    for path in ["dir/old", "dir/sub", "dir"] {
+        env.set_modified(path, earlier)?;
+    }
+    let res = del()
+        .args(&["dir", "--recent=10"])
+        .answer("y")
+        .env(&env)
+        .run()?;
+    eq!(
+        res.prompt,
+        format!(
+            "dir/ (2 files, 1 directory, 18 B, {})\n  old\n  sub/\nDelete directory \"dir\"? [type \"delete\"]",
+            "newest modified just now: sub/new".bright_yellow()
+        )
+    );
+    ensure!(res.code != SUCCESS);
+    ensure!(env.exists("dir/sub/new"));
+    Ok(())
+}
+
+#[test]
+fn highlights_recent_among_multiple() -> Result<()> {
+    let env = env(&["a", "b"])?;
+    env.set_modified("a", std::time::SystemTime::now() - 2 * HOUR)?;
+    let res = del()
+        .args(&["a", "b", "--recent=10"])
+        .answer("delete")
+        .env(&env)
+        .run()?;
+    eq!(
+        res.prompt,
+        format!(
+            "For the following...\na (1 B, modified 2 hours ago)\nb (1 B, {})\n...delete all? [type \"delete\"]",
+            "modified just now".bright_yellow()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    Ok(())
+}
+
+#[test]
+fn older_entries_ask_as_usual() -> Result<()> {
+    let env = env(&["a"])?;
+    env.set_modified("a", std::time::SystemTime::now() - 2 * HOUR)?;
+    let res = del()
+        .args(&["a", "--recent=60"])
+        .answer("y")
+        .env(&env)
+        .run()?;
+    eq!(
+        res.prompt,
+        "Delete file \"a\" (1 B, modified 2 hours ago)? [y/N]"
+    );
+    ensure!(res.code == SUCCESS);
+    Ok(())
+}
@@ -8,7 +8,7 @@ use crate::utils::{del, env};
 #[rstest]
 fn silent_does_not_print_success_messages(#[values("-s", "--silent")] option: &str) -> Result<()> {
     let env = env(&["foo"])?;
-    let res = del().args(&["foo", option]).answer("").env(&env).run()?;
+    let res = del().args(&["foo", option]).answer("y").env(&env).run()?;
     eq!(res.output, "");
     Ok(())
 }
@@ -0,0 +1,63 @@
+use anyhow::{ensure, Ok, Result};
+
+mod utils;
+
+use crate::utils::{del, env, new, SUCCESS};
+
+const DAY: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+#[test]
+fn summarizes_nested_contents() -> Result<()> {
+    let env = env(&["foo/a", "foo/sub/b", "foo/sub/deeper/c"])?;
+    let res = del().args(&["foo"]).answer("y").env(&env).run()?;
+    eq!(
+        res.prompt,
+        "foo/ (3 files, 2 directories, 30 B, newest modified just now)\n  a\n  sub/\nDelete directory \"foo\"? [y/N]"
+    );
+    ensure!(res.code == SUCCESS);
+    Ok(())
+}
+
+#[test]
+fn lists_only_the_first_children() -> Result<()> {
+    let env = env(&[
+        "foo/a", "foo/b", "foo/c", "foo/d", "foo/e", "foo/f", "foo/g",
+    ])?;
+    let res = del().args(&["foo"]).answer("n").env(&env).run()?;
+    eq!(
+        res.prompt,
+        "foo/ (7 files, 35 B, newest modified just now)\n  a\n  b\n  c\n  d\n  e\n  ...and 2 more\nDelete directory \"foo\"? [y/N]"
+    );
+    ensure!(res.code != SUCCESS);
+    ensure!(env.exists("foo/g"));
+    Ok(())
+}
+
+#[test]
+fn shows_newest_modification() -> Result<()> {
+    let env = env(&["foo/a", "foo/b", "old"])?;
+    let now = std::time::SystemTime::now();
+    env.set_modified("foo/a", now - 3 * DAY)?;
+    env.set_modified("foo/b", now - 2 * DAY)?;
+    env.set_modified("foo", now - 5 * DAY)?;
+    env.set_modified("old", now - DAY)?;
+    let res = del().args(&["foo", "old"]).answer("n").env(&env).run()?;
+    eq!(
+        res.prompt,
+        "For the following...\nfoo/ (2 files, 10 B, newest modified 2 days ago)\n  a\n  b\nold (3 B, modified 1 day ago)\n...delete all? [y/N]"
+    );
+    Ok(())
+}
+
+#[test]
+fn describes_empty_directory() -> Result<()> {
+    let env = env(&[])?;
+    new().args(&["foo/"]).env(&env).run()?;
+    let res = del().args(&["foo"]).answer("y").env(&env).run()?;
+    eq!(
+        res.prompt,
+        "foo/ (empty, modified just now)\nDelete directory \"foo\"? [y/N]"
+    );
+    ensure!(!env.exists("foo"));
+    Ok(())
+}
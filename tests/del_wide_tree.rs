@@ -0,0 +1,20 @@
+use anyhow::{ensure, Ok, Result};
+
+mod utils;
+
+use crate::utils::{del, env, SUCCESS};
+
+#[test]
+fn deletes_more_directories_than_can_be_open() -> Result<()> {
+    let files: Vec<_> = (0..500).map(|i| format!("root/d{}/sub/f", i)).collect();
+    let env = env(&files.iter().map(String::as_str).collect::<Vec<_>>())?;
+    let res = del()
+        .args(&["root", "--force"])
+        .open_files_limit(64)
+        .env(&env)
+        .run()?;
+    eq!(res.error, "");
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists("root"));
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{env, mov, SUCCESS};
+
+#[test]
+fn keeps_replaced_file() -> Result<()> {
+    let env = env(&["foo", "bar"])?;
+    let res = mov()
+        .args(&["foo", "bar", "--backup", "--force"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {} (backup: bar~)",
+            "M".bright_green(),
+            "foo".bright_red(),
+            "bar".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("bar")?, "foo");
+    eq!(env.read("bar~")?, "bar");
+    Ok(())
+}
+
+#[test]
+fn numbers_backup_instead_of_replacing_earlier_one() -> Result<()> {
+    let env = env(&["foo", "bar", "bar~"])?;
+    let res = mov()
+        .args(&["foo", "bar", "--backup", "--force"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {} (backup: bar.~1~)",
+            "M".bright_green(),
+            "foo".bright_red(),
+            "bar".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("bar")?, "foo");
+    eq!(env.read("bar~")?, "bar~");
+    eq!(env.read("bar.~1~")?, "bar");
+    Ok(())
+}
+
+#[test]
+fn numbered_keeps_earlier_backups() -> Result<()> {
+    let env = env(&["foo", "bar", "bar.~1~", "bar.~3~"])?;
+    let res = mov()
+        .args(&["foo", "bar", "--backup=numbered", "--force"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("bar.~4~")?, "bar");
+    eq!(env.read("bar.~3~")?, "bar.~3~");
+    eq!(env.read("bar")?, "foo");
+    Ok(())
+}
+
+#[test]
+fn keeps_replaced_directory_in_backup_dir() -> Result<()> {
+    let env = env(&["foo/x", "dest/foo/y"])?;
+    let res = mov()
+        .args(&["foo", "dest/", "--backup-dir=backups", "--force"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("backups/foo~/y")?, "dest/foo/y");
+    eq!(env.read("dest/foo/x")?, "foo/x");
+    ensure!(!env.exists("dest/foo/y"));
+    Ok(())
+}
+
+#[test]
+fn nothing_to_keep_without_conflict() -> Result<()> {
+    let env = env(&["foo"])?;
+    let res = mov().args(&["foo", "bar", "--backup"]).env(&env).run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {}",
+            "M".bright_green(),
+            "foo".bright_red(),
+            "bar".bright_green()
+        )
+    );
+    ensure!(!env.exists("bar~"));
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use anyhow::{ensure, Ok, Result};
+
+mod utils;
+
+use crate::utils::{env, mov, SUCCESS};
+
+#[test]
+fn asks_to_retarget_incoming_symlinks() -> Result<()> {
+    let env = env(&["data/file"])?;
+    env.symlink("../data/file", "app/config")?;
+    let res = mov()
+        .args(&["data", "store", "--check-links"])
+        .answer("y")
+        .env(&env)
+        .run()?;
+    eq!(
+        res.prompt,
+        "For the following symlinks pointing into the moved paths...\napp/config -> ../data/file\n...retarget them to the new location? [Y/n]"
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read_link("app/config")?, "../store/file");
+    eq!(env.read("app/config")?, "data/file");
+    Ok(())
+}
+
+#[test]
+fn declined_keeps_incoming_symlinks() -> Result<()> {
+    let env = env(&["data/file"])?;
+    env.symlink("../data/file", "app/config")?;
+    let res = mov()
+        .args(&["data", "deep/store", "--check-links"])
+        .answer("n")
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    ensure!(env.exists("deep/store/file"));
+    eq!(env.read_link("app/config")?, "../data/file");
+    Ok(())
+}
+
+#[test]
+fn retargets_into_directory_with_fix_links() -> Result<()> {
+    let env = env(&["data/file"])?;
+    env.symlink("data", "current")?;
+    let res = mov()
+        .args(&["data", "archive/", "--check-links", "--fix-links"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.read_link("current")?, "archive/data");
+    Ok(())
+}
+
+#[test]
+fn searches_only_given_scope() -> Result<()> {
+    let env = env(&["data/file"])?;
+    env.symlink("../data/file", "app/config")?;
+    env.symlink("../data/file", "other/config")?;
+    let res = mov()
+        .args(&["data", "store", "--check-links=app", "--fix-links"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.read_link("app/config")?, "../store/file");
+    eq!(env.read_link("other/config")?, "../data/file");
+    Ok(())
+}
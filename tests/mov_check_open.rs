@@ -0,0 +1,97 @@
+use anyhow::{ensure, Ok, Result};
+
+mod utils;
+
+use crate::utils::{env, mov, SUCCESS};
+
+#[test]
+fn asks_before_moving_held_paths() -> Result<()> {
+    let env = env(&["busy/file"])?;
+    let mut process = std::process::Command::new("sleep")
+        .arg("30")
+        .current_dir(env.path("busy"))
+        .spawn()?;
+    let res = mov()
+        .args(&["busy", "moved", "--check-open"])
+        .answer("n")
+        .env(&env)
+        .run();
+    process.kill()?;
+    process.wait()?;
+    let res = res?;
+    ensure!(res
+        .prompt
+        .starts_with("The following processes hold paths open...\n"));
+    ensure!(res.prompt.ends_with("...move them anyway? [y/N]"));
+    ensure!(res.code != SUCCESS);
+    ensure!(env.exists("busy/file"));
+    Ok(())
+}
+
+#[test]
+fn force_prints_warnings() -> Result<()> {
+    let env = env(&["busy/file"])?;
+    let mut process = std::process::Command::new("sleep")
+        .arg("30")
+        .current_dir(env.path("busy"))
+        .spawn()?;
+    let res = mov()
+        .args(&["busy", "moved", "--check-open", "--force"])
+        .env(&env)
+        .run();
+    process.kill()?;
+    process.wait()?;
+    let res = res?;
+    ensure!(res.error.starts_with("Warning: \""));
+    ensure!(res.error.ends_with("sleep (working directory)."));
+    ensure!(res.code == SUCCESS);
+    ensure!(env.exists("moved/file"));
+    Ok(())
+}
+
+#[test]
+fn lists_held_paths_in_the_replace_prompt() -> Result<()> {
+    let env = env(&["busy/file", "moved"])?;
+    let mut process = std::process::Command::new("sleep")
+        .arg("30")
+        .current_dir(env.path("busy"))
+        .spawn()?;
+    let res = mov()
+        .args(&["busy", "moved", "--check-open"])
+        .answer("n")
+        .env(&env)
+        .run();
+    process.kill()?;
+    process.wait()?;
+    let res = res?;
+    ensure!(res
+        .prompt
+        .starts_with("The following processes hold paths open...\n"));
+    ensure!(res.prompt.ends_with(
+        "File \"moved\" (5 B, modified just now) already exists, replace it? [y/N]"
+    ));
+    ensure!(!res.prompt.contains("...move them anyway?"));
+    ensure!(res.code != SUCCESS);
+    ensure!(env.exists("busy/file"));
+    Ok(())
+}
+
+#[test]
+fn ignores_working_directory_of_parent() -> Result<()> {
+    let env = env(&["busy/file"])?;
+    let binary = camino::Utf8Path::new("target/debug/mov").canonicalize_utf8()?;
+    // The shell stays in the moved directory while `mov` runs, like an interactive one would
+    let output = std::process::Command::new("sh")
+        .args([
+            "-c",
+            "\"$0\" ../busy ../moved --check-open; exit $?",
+            binary.as_str(),
+        ])
+        .env("HUMAN_UTILS_TTY", "/nonexistent/tty")
+        .current_dir(env.path("busy"))
+        .output()?;
+    eq!(String::from_utf8(output.stderr)?, "");
+    ensure!(output.status.success());
+    ensure!(env.exists("moved/file"));
+    Ok(())
+}
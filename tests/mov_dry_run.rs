@@ -25,3 +25,39 @@ fn dry_run_doesnt_perform_changes(#[values("-n", "--dry-run")] option: &str) ->
     ensure!(!env.exists("bar"));
     Ok(())
 }
+
+#[test]
+fn dry_run_prints_tree() -> Result<()> {
+    use colored::Colorize;
+
+    let env = env(&["foo", "dogs/rex"])?;
+    let res = mov()
+        .args(&["foo", "dogs/", "--dry-run", "--tree"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {}{}\nBefore:\n📂 .\n├ 📂 dogs\n│ └ 📜 rex\n└ {}\nAfter:\n📂 .\n└ 📂 dogs\n  ├ {}\n  └ 📜 rex",
+            "M".bright_green(),
+            "foo".bright_red(),
+            "dogs/",
+            "foo".bright_green(),
+            "📜 foo".bright_red(),
+            "📜 foo".bright_green()
+        )
+    );
+
+    ensure!(env.exists("foo"));
+    ensure!(!env.exists("dogs/foo"));
+    Ok(())
+}
+
+#[test]
+fn tree_requires_dry_run() -> Result<()> {
+    let env = env(&["foo"])?;
+    let res = mov().args(&["foo", "bar", "--tree"]).env(&env).run()?;
+    ensure!(res.code != utils::SUCCESS);
+    ensure!(env.exists("foo"));
+    Ok(())
+}
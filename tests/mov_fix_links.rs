@@ -0,0 +1,89 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{env, mov, SUCCESS};
+
+#[test]
+fn rewrites_moved_relative_symlink() -> Result<()> {
+    let env = env(&["shared/lib"])?;
+    env.symlink("../shared/lib", "app/lib")?;
+    let res = mov()
+        .args(&["app/lib", "deep/er/lib", "--fix-links"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {}\n{} deep/er/lib: {} -> {}",
+            "M".bright_green(),
+            "app/lib".bright_red(),
+            "deep/er/lib".bright_green(),
+            "L".bright_green(),
+            "../shared/lib".bright_red(),
+            "../../shared/lib".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read_link("deep/er/lib")?, "../../shared/lib");
+    eq!(env.read("deep/er/lib")?, "shared/lib");
+    Ok(())
+}
+
+#[test]
+fn rewrites_symlinks_inside_moved_directory() -> Result<()> {
+    let env = env(&["shared/lib", "pkg/sub/file"])?;
+    env.symlink("../../shared/lib", "pkg/sub/outside")?;
+    env.symlink("../sub/file", "pkg/sub/inside")?;
+    let res = mov()
+        .args(&["pkg", "a/b/", "--fix-links"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(
+        env.read_link("a/b/pkg/sub/outside")?,
+        "../../../../shared/lib"
+    );
+    eq!(env.read_link("a/b/pkg/sub/inside")?, "../sub/file");
+    Ok(())
+}
+
+#[test]
+fn asks_to_rewrite_symlinks() -> Result<()> {
+    let env = env(&["shared/lib"])?;
+    env.symlink("../shared/lib", "app/lib")?;
+    let res = mov()
+        .args(&["app/lib", "deep/er/lib"])
+        .answer("n")
+        .env(&env)
+        .run()?;
+    eq!(
+        res.prompt,
+        "For the following relative symlinks...\napp/lib -> ../shared/lib\n...rewrite their targets so that they still resolve after the move? [Y/n]"
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read_link("deep/er/lib")?, "../shared/lib");
+    Ok(())
+}
+
+#[test]
+fn keeps_symlinks_resolving_at_the_same_depth() -> Result<()> {
+    let env = env(&["shared/lib"])?;
+    env.symlink("../shared/lib", "app/lib")?;
+    let res = mov().args(&["app/lib", "other/lib"]).env(&env).run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.read_link("other/lib")?, "../shared/lib");
+    Ok(())
+}
+
+#[test]
+fn searches_moved_directories_only_on_request() -> Result<()> {
+    let env = env(&["shared/lib", "pkg/file"])?;
+    env.symlink("../shared/lib", "pkg/outside")?;
+    let res = mov().args(&["pkg", "a/b/"]).env(&env).run()?;
+    eq!(res.prompt, "");
+    ensure!(res.code == SUCCESS);
+    eq!(env.read_link("a/b/pkg/outside")?, "../shared/lib");
+    Ok(())
+}
@@ -14,7 +14,8 @@ fn force_does_not_ask_for_confirmation(#[values("-f", "--force")] option: &str)
     eq!(
         res.output,
         format!(
-            "{} {} -> {}",
+            "{}\n{} {} -> {}",
+            "D bar".bright_red(),
             "M".bright_green(),
             "foo".bright_red(),
             "bar".bright_green()
@@ -0,0 +1,75 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{env, mov, SUCCESS};
+
+#[test]
+fn moves_index_entries() -> Result<()> {
+    let env = env(&["src/a.rs", "src/b.rs"])?;
+    env.git_init()?;
+    let res = mov()
+        .args(&["src/a.rs", "src/c.rs", "--git"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> src/{} (staged)",
+            "M".bright_green(),
+            "src/a.rs".bright_red(),
+            "c.rs".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(
+        env.git(&["status", "--porcelain"])?,
+        "R  src/a.rs -> src/c.rs\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn keeps_staged_contents() -> Result<()> {
+    let env = env(&["dir/a"])?;
+    env.git_init()?;
+    env.write("dir/a", "staged")?;
+    env.git(&["add", "dir/a"])?;
+    env.write("dir/a", "not staged")?;
+    let res = mov().args(&["dir", "moved", "--git"]).env(&env).run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.git(&["show", ":moved/a"])?, "staged");
+    eq!(env.read("moved/a")?, "not staged");
+    Ok(())
+}
+
+#[test]
+fn enabled_by_config() -> Result<()> {
+    let env = env(&["a", "b"])?;
+    env.git_init()?;
+    env.git(&["config", "human-utils.git", "true"])?;
+    let res = mov().args(&["a", "b", "--force"]).env(&env).run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.git(&["status", "--porcelain"])?, "D  a\nM  b\n");
+    Ok(())
+}
+
+#[test]
+fn untracked_files_are_not_staged() -> Result<()> {
+    let env = env(&["a"])?;
+    env.git_init()?;
+    env.write("new", "new")?;
+    let res = mov().args(&["new", "moved", "--git"]).env(&env).run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {}",
+            "M".bright_green(),
+            "new".bright_red(),
+            "moved".bright_green()
+        )
+    );
+    eq!(env.git(&["status", "--porcelain"])?, "?? moved\n");
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{env, mov, SUCCESS};
+
+#[test]
+fn requires_typing_replace_for_unsaved_work() -> Result<()> {
+    let env = env(&["src/a", "repo/b"])?;
+    env.git(&["-C", "repo", "init", "--quiet"])?;
+    let res = mov()
+        .args(&["src", "repo"])
+        .answer("replace")
+        .env(&env)
+        .run()?;
+    eq!(
+        res.prompt,
+        format!(
+            "The following git repositories contain work which exists nowhere else...\nrepo/: {}\nrepo/ (3 files, 7 directories, 121 B, newest modified just now)\n  .git/\n  b\nDirectory \"repo\" already exists, replace it? [type \"replace\"]",
+            "1 untracked file".bright_red()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    ensure!(env.read("repo/a")? == "src/a");
+    ensure!(!env.exists("repo/b"));
+    Ok(())
+}
+
+#[test]
+fn yes_keeps_unsaved_work() -> Result<()> {
+    let env = env(&["src/a", "repo/b"])?;
+    env.git(&["-C", "repo", "init", "--quiet"])?;
+    let res = mov().args(&["src", "repo"]).answer("y").env(&env).run()?;
+    ensure!(res.code != SUCCESS);
+    ensure!(env.exists("src/a"));
+    ensure!(env.exists("repo/b"));
+    Ok(())
+}
+
+#[test]
+fn multiple_sources_require_typing_overwrite() -> Result<()> {
+    let env = env(&["a", "repo/b", "dir/repo/c"])?;
+    env.git(&["-C", "dir/repo", "init", "--quiet"])?;
+    let res = mov()
+        .args(&["a", "repo", "dir/"])
+        .answer("overwrite")
+        .env(&env)
+        .run()?;
+    eq!(
+        res.prompt,
+        format!(
+            "The following git repositories contain work which exists nowhere else...\ndir/repo/: {}\ndir/repo/ (3 files, 7 directories, 125 B, newest modified just now)\n  .git/\n  c\nOverwrite directory \"dir/repo\"? [type \"overwrite\"]",
+            "1 untracked file".bright_red()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    ensure!(env.read("dir/repo/b")? == "repo/b");
+    ensure!(!env.exists("dir/repo/c"));
+    Ok(())
+}
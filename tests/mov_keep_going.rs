@@ -0,0 +1,64 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{env, mov, SUCCESS};
+
+const PARTIAL_FAILURE: i32 = 2;
+
+#[test]
+fn moves_the_other_sources() -> Result<()> {
+    let env = env(&["a", "b", "dest/y"])?;
+    let res = mov()
+        .args(&["a", "missing", "b", "dest/", "--keep-going"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {}{}\n{} {} -> {}{}",
+            "M".bright_green(),
+            "a".bright_red(),
+            "dest/",
+            "a".bright_green(),
+            "M".bright_green(),
+            "b".bright_red(),
+            "dest/",
+            "b".bright_green()
+        )
+    );
+    ensure!(res
+        .error
+        .starts_with("Error for \"missing\": No such file or directory"));
+    ensure!(res.error.ends_with("2 done, 1 failed"));
+    eq!(res.code, PARTIAL_FAILURE);
+    eq!(env.read("dest/a")?, "a");
+    eq!(env.read("dest/b")?, "b");
+    Ok(())
+}
+
+#[test]
+fn fails_when_nothing_is_moved() -> Result<()> {
+    let env = env(&["dest/y"])?;
+    let res = mov()
+        .args(&["missing", "dest/", "--keep-going"])
+        .env(&env)
+        .run()?;
+    ensure!(res.error.ends_with("0 done, 1 failed"));
+    ensure!(res.code != SUCCESS && res.code != PARTIAL_FAILURE);
+    Ok(())
+}
+
+#[test]
+fn counts_a_replacement_once() -> Result<()> {
+    let env = env(&["a", "b"])?;
+    let res = mov()
+        .args(&["a", "b", "--keep-going", "--force"])
+        .env(&env)
+        .run()?;
+    ensure!(res.output.ends_with("1 done, 0 failed"));
+    eq!(res.code, SUCCESS);
+    eq!(env.read("b")?, "a");
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{env, mov, SUCCESS};
+
+#[test]
+fn merges_into_existing_directory() -> Result<()> {
+    let env = env(&["a/x", "a/sub/y", "b/x", "b/sub/z"])?;
+    let res = mov()
+        .args(&["a", "b", "--merge"])
+        .answer("y")
+        .env(&env)
+        .run()?;
+    eq!(
+        res.prompt,
+        "Overwrite file \"b/x\" (3 B, modified just now)? [y/N]"
+    );
+    eq!(
+        res.output,
+        format!(
+            "{} {}\n{} {}",
+            "N".bright_green(),
+            "b/sub/y".bright_green(),
+            "E".bright_green(),
+            "b/x".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("b/x")?, "a/x");
+    eq!(env.read("b/sub/y")?, "a/sub/y");
+    eq!(env.read("b/sub/z")?, "b/sub/z");
+    ensure!(!env.exists("a"));
+    Ok(())
+}
+
+#[test]
+fn declined_conflicts_stay_in_source() -> Result<()> {
+    let env = env(&["a/x", "a/y", "b/x"])?;
+    let res = mov()
+        .args(&["a", "b", "--merge"])
+        .answer("n")
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!("{} {}", "N".bright_green(), "b/y".bright_green())
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("b/x")?, "b/x");
+    eq!(env.read("a/x")?, "a/x");
+    ensure!(!env.exists("a/y"));
+    Ok(())
+}
+
+#[test]
+fn moves_new_directories_whole() -> Result<()> {
+    let env = env(&["a/new/deep/file", "b/other"])?;
+    let res = mov().args(&["a", "b", "--merge"]).env(&env).run()?;
+    eq!(
+        res.output,
+        format!("{} {}", "N".bright_green(), "b/new/".bright_green())
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("b/new/deep/file")?, "a/new/deep/file");
+    ensure!(env.exists("b/other"));
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{env, mov, SUCCESS};
+
+#[test]
+fn skip_keeps_existing() -> Result<()> {
+    let env = env(&["foo", "bar"])?;
+    let res = mov()
+        .args(&["foo", "bar", "--on-conflict=skip"])
+        .env(&env)
+        .run()?;
+    eq!(res.prompt, "");
+    eq!(res.output, "Skipped foo -> bar (on conflict: skip)");
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("foo")?, "foo");
+    eq!(env.read("bar")?, "bar");
+    Ok(())
+}
+
+#[test]
+fn rename_keeps_both() -> Result<()> {
+    let env = env(&["foo.txt", "bar.txt", "bar (1).txt"])?;
+    let res = mov()
+        .args(&["foo.txt", "bar.txt", "--on-conflict=rename"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {}{}{} (on conflict: rename)",
+            "M".bright_green(),
+            "foo.txt".bright_red(),
+            "\"".bright_green(),
+            "bar (2).txt".bright_green(),
+            "\"".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists("foo.txt"));
+    eq!(env.read("bar.txt")?, "bar.txt");
+    eq!(env.read("bar (2).txt")?, "foo.txt");
+    Ok(())
+}
+
+#[test]
+fn newer_decides_per_source() -> Result<()> {
+    let env = env(&["a/x", "a/y", "b/x", "b/y"])?;
+    let now = SystemTime::now();
+    env.set_modified("b/x", now - Duration::from_secs(3600))?;
+    env.set_modified("b/y", now + Duration::from_secs(3600))?;
+    let res = mov()
+        .args(&["a/x", "a/y", "b/", "--on-conflict=newer"])
+        .env(&env)
+        .run()?;
+    eq!(res.prompt, "");
+    eq!(
+        res.output,
+        format!(
+            "{}\n{} {} -> b/{} (on conflict: newer)\nSkipped a/y -> b/y (on conflict: newer)",
+            "D b/x".bright_red(),
+            "M".bright_green(),
+            "a/x".bright_red(),
+            "x".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("b/x")?, "a/x");
+    eq!(env.read("b/y")?, "b/y");
+    ensure!(env.exists("a/y"));
+    Ok(())
+}
+
+#[test]
+fn conflicts_with_merge() -> Result<()> {
+    let env = env(&["a/x", "b/x"])?;
+    let res = mov()
+        .args(&["a", "b", "--merge", "--on-conflict=skip"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code != SUCCESS);
+    eq!(env.read("b/x")?, "b/x");
+    Ok(())
+}
+
+#[test]
+fn skip_keeps_broken_symlink_in_directory() -> Result<()> {
+    let env = env(&["foo", "bar/other"])?;
+    env.symlink("missing", "bar/foo")?;
+    let res = mov()
+        .args(&["foo", "bar/", "--on-conflict=skip"])
+        .env(&env)
+        .run()?;
+    eq!(res.output, "Skipped foo -> bar/foo (on conflict: skip)");
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("foo")?, "foo");
+    eq!(env.read_link("bar/foo")?, "missing");
+    Ok(())
+}
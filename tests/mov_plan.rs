@@ -0,0 +1,67 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{env, mov, SUCCESS};
+
+#[test]
+fn sources_with_the_same_name_collide() -> Result<()> {
+    let env = env(&["a/x", "b/x", "dest/y"])?;
+    let res = mov().args(&["a/x", "b/x", "dest/"]).env(&env).run()?;
+    eq!(
+        res.error,
+        "Error: Cannot write both \"a/x\" and \"b/x\" to \"dest/x\""
+    );
+    ensure!(res.code != SUCCESS);
+    eq!(env.read("a/x")?, "a/x");
+    eq!(env.read("b/x")?, "b/x");
+    ensure!(!env.exists("dest/x"));
+    Ok(())
+}
+
+#[test]
+fn cannot_move_into_itself() -> Result<()> {
+    let env = env(&["foo/a"])?;
+    let res = mov().args(&["foo", "foo/"]).env(&env).run()?;
+    eq!(
+        res.error,
+        "Error: Cannot move \"foo\" into itself at \"foo/foo\""
+    );
+    ensure!(res.code != SUCCESS);
+    eq!(env.read("foo/a")?, "foo/a");
+    Ok(())
+}
+
+#[test]
+fn source_inside_another_source_fails() -> Result<()> {
+    let env = env(&["a/x", "dest/y"])?;
+    let res = mov().args(&["a", "a/x", "dest/"]).env(&env).run()?;
+    eq!(res.error, "Error: \"a/x\" is inside \"a\", which is moved");
+    ensure!(res.code != SUCCESS);
+    eq!(env.read("a/x")?, "a/x");
+    Ok(())
+}
+
+#[test]
+fn dry_run_lists_replaced_entries() -> Result<()> {
+    let env = env(&["foo", "bar/baz"])?;
+    let res = mov()
+        .args(&["foo", "bar", "--dry-run", "--force"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{}\n{} {} -> {}",
+            "D bar/".bright_red(),
+            "M".bright_green(),
+            "foo".bright_red(),
+            "bar".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("bar/baz")?, "bar/baz");
+    ensure!(env.exists("foo"));
+    Ok(())
+}
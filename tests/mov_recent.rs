@@ -0,0 +1,33 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{env, mov, SUCCESS};
+
+#[test]
+fn requires_typing_replace_for_recent_file() -> Result<()> {
+    let env = env(&["foo", "bar"])?;
+    let res = mov()
+        .args(&["foo", "bar", "--recent=10"])
+        .answer("y")
+        .env(&env)
+        .run()?;
+    eq!(
+        res.prompt,
+        format!(
+            "File \"bar\" (3 B, {}) already exists, replace it? [type \"replace\"]",
+            "modified just now".bright_yellow()
+        )
+    );
+    ensure!(res.code != SUCCESS);
+    eq!(env.read("bar")?, "bar");
+    let res = mov()
+        .args(&["foo", "bar", "--recent=10"])
+        .answer("replace")
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("bar")?, "foo");
+    Ok(())
+}
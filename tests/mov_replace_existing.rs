@@ -9,12 +9,16 @@ use crate::utils::{env, mov, SUCCESS};
 #[test]
 fn asks_for_confirmation() -> Result<()> {
     let env = env(&["foo", "bar"])?;
-    let res = mov().args(&["foo", "bar"]).answer("").env(&env).run()?;
-    eq!(res.prompt, "File \"bar\" already exists, replace it? [Y/n]");
+    let res = mov().args(&["foo", "bar"]).answer("y").env(&env).run()?;
+    eq!(
+        res.prompt,
+        "File \"bar\" (3 B, modified just now) already exists, replace it? [y/N]"
+    );
     eq!(
         res.output,
         format!(
-            "{} {} -> {}",
+            "{}\n{} {} -> {}",
+            "D bar".bright_red(),
             "M".bright_green(),
             "foo".bright_red(),
             "bar".bright_green()
@@ -41,7 +45,21 @@ fn valid_confirmations(#[values("y", "Y", "yes")] value: &str) -> Result<()> {
 fn without_confirmation_does_nothing() -> Result<()> {
     let env = env(&["foo", "bar"])?;
     let res = mov().args(&["foo", "bar"]).answer("n").env(&env).run()?;
-    eq!(res.prompt, "File \"bar\" already exists, replace it? [Y/n]");
+    eq!(
+        res.prompt,
+        "File \"bar\" (3 B, modified just now) already exists, replace it? [y/N]"
+    );
+    eq!(res.output, "");
+    ensure!(res.code != SUCCESS);
+    ensure!(env.exists("foo"));
+    ensure!(env.read("bar")? == "bar");
+    Ok(())
+}
+
+#[test]
+fn empty_answer_does_nothing() -> Result<()> {
+    let env = env(&["foo", "bar"])?;
+    let res = mov().args(&["foo", "bar"]).answer("").env(&env).run()?;
     eq!(res.output, "");
     ensure!(res.code != SUCCESS);
     ensure!(env.exists("foo"));
@@ -63,15 +81,16 @@ fn valid_rejections(#[values("n", "N", "no", "boo")] value: &str) -> Result<()>
 #[test]
 fn file_replacing_dir() -> Result<()> {
     let env = env(&["foo", "bar/baz"])?;
-    let res = mov().args(&["foo", "bar"]).answer("\n").env(&env).run()?;
+    let res = mov().args(&["foo", "bar"]).answer("y").env(&env).run()?;
     eq!(
         res.prompt,
-        "Directory \"bar\" already exists, replace it? [Y/n]"
+        "bar/ (1 file, 7 B, newest modified just now)\n  baz\nDirectory \"bar\" already exists, replace it? [y/N]"
     );
     eq!(
         res.output,
         format!(
-            "{} {} -> {}",
+            "{}\n{} {} -> {}",
+            "D bar/".bright_red(),
             "M".bright_green(),
             "foo".bright_red(),
             "bar".bright_green()
@@ -85,12 +104,16 @@ fn file_replacing_dir() -> Result<()> {
 #[test]
 fn dir_replacing_file() -> Result<()> {
     let env = env(&["foo/baz", "bar"])?;
-    let res = mov().args(&["foo", "bar"]).answer("\n").env(&env).run()?;
-    eq!(res.prompt, "File \"bar\" already exists, replace it? [Y/n]");
+    let res = mov().args(&["foo", "bar"]).answer("y").env(&env).run()?;
+    eq!(
+        res.prompt,
+        "File \"bar\" (3 B, modified just now) already exists, replace it? [y/N]"
+    );
     eq!(
         res.output,
         format!(
-            "{} {} -> {}",
+            "{}\n{} {} -> {}",
+            "D bar".bright_red(),
             "M".bright_green(),
             "foo".bright_red(),
             "bar".bright_green()
@@ -104,15 +127,16 @@ fn dir_replacing_file() -> Result<()> {
 #[test]
 fn dir_replacing_dir() -> Result<()> {
     let env = env(&["foo/baz", "bar/lorem"])?;
-    let res = mov().args(&["foo", "bar"]).answer("\n").env(&env).run()?;
+    let res = mov().args(&["foo", "bar"]).answer("y").env(&env).run()?;
     eq!(
         res.prompt,
-        "Directory \"bar\" already exists, replace it? [Y/n]"
+        "bar/ (1 file, 9 B, newest modified just now)\n  lorem\nDirectory \"bar\" already exists, replace it? [y/N]"
     );
     eq!(
         res.output,
         format!(
-            "{} {} -> {}",
+            "{}\n{} {} -> {}",
+            "D bar/".bright_red(),
             "M".bright_green(),
             "foo".bright_red(),
             "bar".bright_green()
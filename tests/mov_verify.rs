@@ -0,0 +1,48 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{env, mov, SUCCESS};
+
+#[test]
+fn renames_without_copying() -> Result<()> {
+    let env = env(&["foo"])?;
+    let res = mov().args(&["foo", "bar", "--verify"]).env(&env).run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {}",
+            "M".bright_green(),
+            "foo".bright_red(),
+            "bar".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("bar")?, "foo");
+    Ok(())
+}
+
+#[test]
+fn writes_manifest_after_rename() -> Result<()> {
+    let env = env(&["foo"])?;
+    let res = mov()
+        .args(&["foo", "bar", "--verify", "--manifest"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} -> {} (checksums in bar.sha256)",
+            "M".bright_green(),
+            "foo".bright_red(),
+            "bar".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(
+        env.read("bar.sha256")?,
+        "2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae  bar\n"
+    );
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+use human_utils::SUCCESS;
+
+mod utils;
+
+use crate::utils::{env, new};
+
+#[test]
+fn keeps_overwritten_file() -> Result<()> {
+    let env = env(&["a"])?;
+    let res = new()
+        .args(&["a", "--backup", "--force", "--", "text"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!("{} {} (backup: a~)", "E".bright_green(), "a".bright_green())
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("a")?, "text\n");
+    eq!(env.read("a~")?, "a");
+    Ok(())
+}
+
+#[test]
+fn keeps_directory_replaced_by_file() -> Result<()> {
+    let env = env(&["a/b"])?;
+    let res = new()
+        .args(&["a", "--backup=numbered", "--force"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{}{}\n{} {}",
+            "D a/".bright_red(),
+            " (backup: a.~1~)",
+            "N".bright_green(),
+            "a".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("a")?, "");
+    eq!(env.read("a.~1~/b")?, "a/b");
+    Ok(())
+}
+
+#[test]
+fn dry_run_keeps_nothing() -> Result<()> {
+    let env = env(&["a"])?;
+    let res = new()
+        .args(&["a", "--backup", "--force", "--dry-run", "--", "text"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("a")?, "a");
+    ensure!(!env.exists("a~"));
+    Ok(())
+}
+
+#[test]
+fn numbers_backups_with_the_same_name_in_backup_dir() -> Result<()> {
+    let env = env(&["x/a", "y/a"])?;
+    let res = new()
+        .args(&["x/a", "y/a", "--backup-dir=bk", "--force", "--", "text"])
+        .env(&env)
+        .run()?;
+    eq!(res.error, "");
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("x/a")?, "text\n");
+    eq!(env.read("y/a")?, "text\n");
+    eq!(env.read("bk/a~")?, "x/a");
+    eq!(env.read("bk/a.~1~")?, "y/a");
+    Ok(())
+}
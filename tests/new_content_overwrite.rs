@@ -28,7 +28,10 @@ fn prompts_to_overwrite_file_with_content() -> Result<()> {
         .answer("y")
         .env(&env)
         .run()?;
-    eq!(res.prompt, "Overwrite file \"a\"? [Y/n]");
+    eq!(
+        res.prompt,
+        "Overwrite file \"a\" (13 B, modified just now)? [y/N]"
+    );
     eq!(
         res.output,
         format!("{} {}", "E".bright_green(), "a".bright_green(),)
@@ -43,7 +46,10 @@ fn prompts_to_erase_file() -> Result<()> {
     let env = env(&["a"])?;
     env.write("a", "So important!")?;
     let res = new().args(&["a", "--", ""]).answer("y").env(&env).run()?;
-    eq!(res.prompt, "Overwrite file \"a\"? [Y/n]");
+    eq!(
+        res.prompt,
+        "Overwrite file \"a\" (13 B, modified just now)? [y/N]"
+    );
     eq!(
         res.output,
         format!("{} {}", "E".bright_green(), "a".bright_green(),)
@@ -31,3 +31,25 @@ fn dry_run_does_not_create_directory() -> Result<()> {
     ensure!(!env.exists_directory("a"));
     Ok(())
 }
+
+#[test]
+fn dry_run_prints_tree() -> Result<()> {
+    let env = env(&["b"])?;
+    let res = new()
+        .args(&["a/c", "--dry-run", "--tree"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {}\nBefore:\n📂 .\n└ 📜 b\nAfter:\n📂 .\n├ {}\n│ └ {}\n└ 📜 b",
+            "N".bright_green(),
+            "a/c".bright_green(),
+            "📂 a".bright_green(),
+            "📜 c".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    ensure!(!env.exists_directory("a"));
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use anyhow::{ensure, Ok, Result};
+
+mod utils;
+
+use crate::utils::{env, new, SUCCESS};
+
+#[test]
+fn writes_the_created_directory() -> Result<()> {
+    let env = env(&[])?;
+    let res = new()
+        .args(&["--track-cwd-change", "tracking", "--enter", "foo/bar/"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    ensure!(env.exists_directory("foo/bar"));
+    let new_cwd = env.read("tracking")?;
+    ensure!(new_cwd.starts_with('/') && new_cwd.ends_with("/foo/bar"));
+    Ok(())
+}
+
+#[test]
+fn writes_an_existing_directory() -> Result<()> {
+    let env = env(&["foo/a"])?;
+    let res = new()
+        .args(&["--track-cwd-change", "tracking", "--enter", "-d", "foo"])
+        .env(&env)
+        .run()?;
+    ensure!(res.code == SUCCESS);
+    ensure!(env.read("tracking")?.ends_with("/foo"));
+    Ok(())
+}
+
+#[test]
+fn expects_one_directory() -> Result<()> {
+    let env = env(&[])?;
+    let res = new()
+        .args(&["--track-cwd-change", "tracking", "--enter", "a/", "b/"])
+        .env(&env)
+        .run()?;
+    eq!(res.error, "Error: Expected 1 directory to enter, but got 2");
+    ensure!(res.code != SUCCESS);
+    ensure!(!env.exists("a") && !env.exists("tracking"));
+    Ok(())
+}
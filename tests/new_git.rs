@@ -0,0 +1,36 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+use human_utils::SUCCESS;
+
+mod utils;
+
+use crate::utils::{env, new};
+
+#[test]
+fn adds_new_files() -> Result<()> {
+    let env = env(&["a"])?;
+    env.git_init()?;
+    let res = new().args(&["b", "--git"]).env(&env).run()?;
+    eq!(
+        res.output,
+        format!("{} {} (staged)", "N".bright_green(), "b".bright_green())
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.git(&["status", "--porcelain"])?, "A  b\n");
+    Ok(())
+}
+
+#[test]
+fn skips_ignored_files() -> Result<()> {
+    let env = env(&[".gitignore"])?;
+    env.write(".gitignore", "*.log\n")?;
+    env.git_init()?;
+    let res = new().args(&["x.log", "--git"]).env(&env).run()?;
+    eq!(
+        res.output,
+        format!("{} {}", "N".bright_green(), "x.log".bright_green())
+    );
+    eq!(res.error, "");
+    eq!(env.git(&["status", "--porcelain"])?, "");
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+
+mod utils;
+
+use crate::utils::{env, new};
+
+const PARTIAL_FAILURE: i32 = 2;
+
+#[test]
+fn creates_the_other_files_when_one_fails() -> Result<()> {
+    let env = env(&[])?;
+    // Passes the validation of the plan and only fails when the file is created
+    let too_long = "x".repeat(300);
+    let res = new()
+        .args(&["a", &too_long, "b", "--keep-going"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {}\n{} {}",
+            "N".bright_green(),
+            "a".bright_green(),
+            "N".bright_green(),
+            "b".bright_green()
+        )
+    );
+    ensure!(res
+        .error
+        .starts_with(&format!("Error for \"{}\": File name too long", too_long)));
+    ensure!(res.error.ends_with("2 done, 1 failed"));
+    eq!(res.code, PARTIAL_FAILURE);
+    eq!(env.read("a")?, "");
+    eq!(env.read("b")?, "");
+    Ok(())
+}
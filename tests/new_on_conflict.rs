@@ -0,0 +1,88 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+use human_utils::SUCCESS;
+
+mod utils;
+
+use crate::utils::{env, new};
+
+#[test]
+fn skip_keeps_existing_file() -> Result<()> {
+    let env = env(&["a"])?;
+    let res = new()
+        .args(&["a", "b", "--on-conflict=skip", "--", "text"])
+        .env(&env)
+        .run()?;
+    eq!(res.prompt, "");
+    eq!(
+        res.output,
+        format!(
+            "Skipped a (on conflict: skip)\n{} {}",
+            "N".bright_green(),
+            "b".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("a")?, "a");
+    eq!(env.read("b")?, "text\n");
+    Ok(())
+}
+
+#[test]
+fn skip_keeps_file_in_the_way_of_directory() -> Result<()> {
+    let env = env(&["a"])?;
+    let res = new()
+        .args(&["a/b/", "a/c", "--on-conflict=skip"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        "Skipped a/b/ (on conflict: skip)\nSkipped a/c (on conflict: skip)"
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("a")?, "a");
+    Ok(())
+}
+
+#[test]
+fn rename_creates_sibling() -> Result<()> {
+    let env = env(&["a.txt"])?;
+    let res = new()
+        .args(&["a.txt", "--on-conflict=rename", "--", "text"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {}{}{} (on conflict: rename)",
+            "N".bright_green(),
+            "\"".bright_green(),
+            "a (1).txt".bright_green(),
+            "\"".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("a.txt")?, "a.txt");
+    eq!(env.read("a (1).txt")?, "text\n");
+    Ok(())
+}
+
+#[test]
+fn larger_replaces_smaller_file() -> Result<()> {
+    let env = env(&["a"])?;
+    let res = new()
+        .args(&["a", "--on-conflict=larger", "--", "more text"])
+        .env(&env)
+        .run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {} (on conflict: larger)",
+            "E".bright_green(),
+            "a".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    eq!(env.read("a")?, "more text\n");
+    Ok(())
+}
@@ -10,7 +10,10 @@ use crate::utils::{env, new};
 fn prompts_to_erase_directory() -> Result<()> {
     let env = env(&["a/b"])?;
     let res = new().args(&["a"]).answer("y").env(&env).run()?;
-    eq!(res.prompt, "Overwrite directory \"a\"? [Y/n]");
+    eq!(
+        res.prompt,
+        "a/ (1 file, 3 B, newest modified just now)\n  b\nOverwrite directory \"a\"? [y/N]"
+    );
     eq!(
         res.output,
         format!(
@@ -28,7 +31,10 @@ fn prompts_to_erase_directory() -> Result<()> {
 fn prompts_to_erase_nested_directory() -> Result<()> {
     let env = env(&["a/b/c"])?;
     let res = new().args(&["a/b"]).answer("y").env(&env).run()?;
-    eq!(res.prompt, "Overwrite directory \"a/b\"? [Y/n]");
+    eq!(
+        res.prompt,
+        "a/b/ (1 file, 5 B, newest modified just now)\n  c\nOverwrite directory \"a/b\"? [y/N]"
+    );
     eq!(
         res.output,
         format!(
@@ -46,7 +52,10 @@ fn prompts_to_erase_nested_directory() -> Result<()> {
 fn prompts_to_erase_nested_file() -> Result<()> {
     let env = env(&["a/b"])?;
     let res = new().args(&["a/b/c"]).answer("y").env(&env).run()?;
-    eq!(res.prompt, "Overwrite file \"a/b\"? [Y/n]");
+    eq!(
+        res.prompt,
+        "Overwrite file \"a/b\" (3 B, modified just now)? [y/N]"
+    );
     eq!(
         res.output,
         format!(
@@ -0,0 +1,26 @@
+use anyhow::{ensure, Ok, Result};
+use colored::Colorize;
+use human_utils::SUCCESS;
+
+mod utils;
+
+use crate::utils::{env, new};
+
+#[test]
+fn quotes_paths_with_spaces_once() -> Result<()> {
+    let env = env(&[])?;
+    let res = new().args(&["a b"]).env(&env).run()?;
+    eq!(
+        res.output,
+        format!(
+            "{} {}{}{}",
+            "N".bright_green(),
+            "\"".bright_green(),
+            "a b".bright_green(),
+            "\"".bright_green()
+        )
+    );
+    ensure!(res.code == SUCCESS);
+    ensure!(env.exists("a b"));
+    Ok(())
+}
@@ -23,6 +23,8 @@ pub struct Runner<'a> {
     args: Vec<String>,
     env: Option<&'a Environment>,
     answer: Option<String>,
+    terminal: bool,
+    open_files_limit: Option<usize>,
 }
 
 pub struct Environment {
@@ -39,7 +41,7 @@ pub fn env(description: &[&str]) -> Result<Environment> {
         description: description.iter().map(|s| s.to_string()).collect(),
     };
     create_environment(&env)?;
-    return Ok(env);
+    Ok(env)
 }
 
 impl Environment {
@@ -57,16 +59,171 @@ impl Environment {
             .is_ok_and(|metadata| metadata.is_dir())
     }
 
+    #[allow(dead_code)]
+    pub fn path(&self, name: &str) -> std::path::PathBuf {
+        self.dir.path().join(name)
+    }
+
     #[allow(dead_code)]
     pub fn read(&self, name: &str) -> Result<String> {
         Ok(std::fs::read_to_string(self.dir.path().join(name))?)
     }
 
+    #[allow(dead_code)]
+    pub fn read_bytes(&self, name: &str) -> Result<Vec<u8>> {
+        Ok(std::fs::read(self.dir.path().join(name))?)
+    }
+
+    #[allow(dead_code)]
+    pub fn write_bytes(&self, name: &str, content: &[u8]) -> Result<()> {
+        Ok(std::fs::write(self.dir.path().join(name), content)?)
+    }
+
     #[allow(dead_code)]
     pub fn write(&self, name: &str, content: &str) -> Result<()> {
         Ok(std::fs::write(self.dir.path().join(name), content)?)
     }
 
+    #[allow(dead_code)]
+    pub fn symlink(&self, target: &str, name: &str) -> Result<()> {
+        let path = self.dir.path().join(name);
+        std::fs::create_dir_all(path.parent().context("Failed to get parent path")?)?;
+        Ok(std::os::unix::fs::symlink(target, path)?)
+    }
+
+    #[allow(dead_code)]
+    pub fn read_link(&self, name: &str) -> Result<String> {
+        let target = std::fs::read_link(self.dir.path().join(name))?;
+        Ok(target
+            .to_str()
+            .context("Link target is not UTF-8")?
+            .to_owned())
+    }
+
+    #[allow(dead_code)]
+    pub fn hard_link(&self, existing: &str, name: &str) -> Result<()> {
+        Ok(std::fs::hard_link(
+            self.dir.path().join(existing),
+            self.dir.path().join(name),
+        )?)
+    }
+
+    #[allow(dead_code)]
+    pub fn is_same_file(&self, a: &str, b: &str) -> Result<bool> {
+        use std::os::unix::fs::MetadataExt;
+        let a = std::fs::metadata(self.dir.path().join(a))?;
+        let b = std::fs::metadata(self.dir.path().join(b))?;
+        Ok(a.dev() == b.dev() && a.ino() == b.ino())
+    }
+
+    #[allow(dead_code)]
+    pub fn set_mode(&self, name: &str, mode: u32) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        let permissions = std::fs::Permissions::from_mode(mode);
+        Ok(std::fs::set_permissions(
+            self.dir.path().join(name),
+            permissions,
+        )?)
+    }
+
+    #[allow(dead_code)]
+    pub fn mode(&self, name: &str) -> Result<u32> {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = std::fs::symlink_metadata(self.dir.path().join(name))?;
+        Ok(metadata.permissions().mode() & 0o7777)
+    }
+
+    #[allow(dead_code)]
+    pub fn set_modified(&self, name: &str, time: std::time::SystemTime) -> Result<()> {
+        let file = std::fs::File::open(self.dir.path().join(name))?;
+        Ok(file.set_modified(time)?)
+    }
+
+    #[allow(dead_code)]
+    pub fn modified(&self, name: &str) -> Result<std::time::SystemTime> {
+        Ok(std::fs::symlink_metadata(self.dir.path().join(name))?.modified()?)
+    }
+
+    #[allow(dead_code)]
+    pub fn set_xattr(&self, name: &str, attribute: &str, value: &str) -> Result<()> {
+        let path = self.dir.path().join(name);
+        let flags = rustix::fs::XattrFlags::empty();
+        Ok(rustix::fs::setxattr(
+            &path,
+            attribute,
+            value.as_bytes(),
+            flags,
+        )?)
+    }
+
+    #[allow(dead_code)]
+    pub fn xattr(&self, name: &str, attribute: &str) -> Result<String> {
+        let mut value = vec![0; 256];
+        let length = rustix::fs::getxattr(self.dir.path().join(name), attribute, &mut value)?;
+        Ok(String::from_utf8(value[..length].to_vec())?)
+    }
+
+    /// Writes `content` at `offset` into a file of `length` bytes, with holes around it.
+    #[allow(dead_code)]
+    pub fn write_sparse(&self, name: &str, length: u64, offset: u64, content: &str) -> Result<()> {
+        use std::os::unix::fs::FileExt;
+        let file = std::fs::File::create(self.dir.path().join(name))?;
+        file.set_len(length)?;
+        Ok(file.write_all_at(content.as_bytes(), offset)?)
+    }
+
+    /// The number of bytes taken up on disk.
+    #[allow(dead_code)]
+    pub fn allocated(&self, name: &str) -> Result<u64> {
+        use std::os::unix::fs::MetadataExt;
+        Ok(std::fs::symlink_metadata(self.dir.path().join(name))?.blocks() * 512)
+    }
+
+    /// Runs git in the environment, returns its output. Repositories are created without
+    /// templates and commits get a fixed date, so that their contents are the same in every run.
+    #[allow(dead_code)]
+    pub fn git(&self, args: &[&str]) -> Result<String> {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(self.dir.path())
+            .env("GIT_CONFIG_GLOBAL", "/dev/null")
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env("GIT_TEMPLATE_DIR", "")
+            .env("GIT_AUTHOR_DATE", "2000-01-01T00:00:00Z")
+            .env("GIT_COMMITTER_DATE", "2000-01-01T00:00:00Z")
+            .output()
+            .context("Failed to run git")?;
+        anyhow::ensure!(
+            output.status.success(),
+            "git {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Ok(String::from_utf8(output.stdout)?)
+    }
+
+    /// Changes the inode flags of `name` with chattr, returns whether that is supported.
+    #[allow(dead_code)]
+    pub fn chattr(&self, flags: &str, name: &str) -> bool {
+        std::process::Command::new("chattr")
+            .args([flags, name])
+            .current_dir(self.dir.path())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    /// Turns the environment into a git repository with all files committed.
+    #[allow(dead_code)]
+    pub fn git_init(&self) -> Result<()> {
+        self.git(&["init", "--quiet"])?;
+        self.git(&["config", "user.name", "Test"])?;
+        self.git(&["config", "user.email", "test@example.com"])?;
+        self.git(&["add", "--all"])?;
+        self.git(&["commit", "--quiet", "--message", "Initial"])?;
+        Ok(())
+    }
+
     #[allow(dead_code)]
     pub fn debug(&self) {
         println!(
@@ -83,6 +240,11 @@ pub fn mov() -> Runner<'static> {
     Runner::new("target/debug/mov")
 }
 
+#[allow(dead_code)]
+pub fn cop() -> Runner<'static> {
+    Runner::new("target/debug/cop")
+}
+
 #[allow(dead_code)]
 pub fn del() -> Runner<'static> {
     Runner::new("target/debug/del")
@@ -100,6 +262,8 @@ impl<'a> Runner<'a> {
             args: Vec::new(),
             env: None,
             answer: None,
+            terminal: true,
+            open_files_limit: None,
         }
     }
 
@@ -115,6 +279,19 @@ impl<'a> Runner<'a> {
         self
     }
 
+    #[allow(dead_code)]
+    pub fn without_terminal(mut self) -> Self {
+        self.terminal = false;
+        self
+    }
+
+    /// Runs the binary through `sh` with `ulimit -n`.
+    #[allow(dead_code)]
+    pub fn open_files_limit(mut self, limit: usize) -> Self {
+        self.open_files_limit = Some(limit);
+        self
+    }
+
     #[allow(dead_code)]
     pub fn env(mut self, env: &'a Environment) -> Self {
         self.env = Some(env);
@@ -124,19 +301,41 @@ impl<'a> Runner<'a> {
     pub fn run(self) -> Result<Outcome> {
         let default_binding = env(&[])?;
         let current_env = self.env.unwrap_or(&default_binding);
-        let mut process = std::process::Command::new(
-            Utf8Path::new(&self.binary)
-                .canonicalize_utf8()
-                .context("Failed to convert target path to canonical")?,
-        )
-        .args(self.args)
-        .env("CLICOLOR_FORCE", "1")
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .current_dir(current_env.dir.path())
-        .spawn()
-        .context("Failed to execute the binary")?;
+        let binary = Utf8Path::new(&self.binary)
+            .canonicalize_utf8()
+            .context("Failed to convert target path to canonical")?;
+        let mut command = match self.open_files_limit {
+            Some(limit) => {
+                let mut command = std::process::Command::new("sh");
+                command.args([
+                    "-c",
+                    &format!("ulimit -n {} && exec \"$0\" \"$@\"", limit),
+                    binary.as_str(),
+                ]);
+                command
+            }
+            None => std::process::Command::new(binary),
+        };
+        let mut process = command
+            .args(self.args)
+            .env("CLICOLOR_FORCE", "1")
+            // The files of the tests were all just created, `--recent` is tested explicitly
+            .env("HUMAN_UTILS_RECENT", "0")
+            // Answers are read from the terminal, point it at the piped stdin instead
+            .env(
+                "HUMAN_UTILS_TTY",
+                if self.terminal {
+                    "/dev/stdin"
+                } else {
+                    "/nonexistent/tty"
+                },
+            )
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .current_dir(current_env.dir.path())
+            .spawn()
+            .context("Failed to execute the binary")?;
         let mut stdout = process.stdout.take().context("Failed to open stdout")?;
         let mut stderr = process.stderr.take().context("Failed to open stderr")?;
         let mut stdin = process.stdin.take().context("Failed to open stdin")?;
@@ -145,7 +344,7 @@ impl<'a> Runner<'a> {
         let mut error = String::new();
         if let Some(answer) = self.answer {
             read_until(']', &mut prompt, &mut stdout)?;
-            write!(stdin, "{}\n", answer)?;
+            writeln!(stdin, "{}", answer)?;
         }
         stdout
             .read_to_string(&mut output)